@@ -0,0 +1,167 @@
+//! The `--allowlist` file of `cargo public-api diff`. It lists changes to the
+//! public API that have been accepted, so that `--deny` only fails on changes
+//! that have not. It is a TOML file that looks like this:
+//!
+//! ```toml
+//! [[accepted]]
+//! path = "example_api::function"
+//! reason = "A new parameter was needed for #123"
+//!
+//! [[accepted]]
+//! text = "pub struct example_api::Struct"
+//! ```
+//!
+//! An entry with a `path` accepts all removed, changed and added items with
+//! that path, except `impl`s. An `impl` has the path of its type, but an entry
+//! for a type does not accept e.g. a removed `impl Send` for it, so such
+//! changes need an entry with a `text`. An entry with a `text` accepts items
+//! rendered exactly like that. A changed item is accepted if its old or new
+//! version is accepted.
+
+use std::fmt::Display;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use public_api::{ItemKind, PublicItem, diff::PublicApiDiff};
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Allowlist {
+    #[serde(default)]
+    accepted: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    path: Option<String>,
+    text: Option<String>,
+
+    /// Why the change was accepted. Only for humans.
+    #[allow(dead_code)]
+    reason: Option<String>,
+}
+
+impl Allowlist {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let toml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read allowlist at {path:?}"))?;
+        let allowlist: Self = toml::from_str(&toml)
+            .with_context(|| format!("Failed to parse allowlist at {path:?}"))?;
+
+        for entry in &allowlist.accepted {
+            if entry.path.is_some() == entry.text.is_some() {
+                bail!(
+                    "Every entry in the allowlist at {path:?} must have either a `path` or a `text`"
+                );
+            }
+        }
+
+        Ok(allowlist)
+    }
+
+    /// If `item` is accepted by any entry.
+    pub fn accepts(&self, item: &PublicItem) -> bool {
+        self.accepted.iter().any(|entry| entry.matches(item))
+    }
+
+    /// Entries that do not accept any item in `diff`, e.g. because the change
+    /// they were added for has since been published. They are good to remove.
+    pub fn stale_entries<'a>(
+        &'a self,
+        diff: &'a PublicApiDiff,
+    ) -> impl Iterator<Item = impl Display + 'a> + 'a {
+        let items: Vec<&PublicItem> = diff
+            .removed
+            .iter()
+            .chain(
+                diff.changed
+                    .iter()
+                    .chain(&diff.deprecated)
+                    .chain(&diff.moved)
+                    .flat_map(|c| [&c.old, &c.new]),
+            )
+            .chain(diff.added.iter())
+            .collect();
+        self.accepted
+            .iter()
+            .filter(move |entry| !items.iter().any(|item| entry.matches(item)))
+    }
+}
+
+/// Allowlist entries that did not match any change. With `--workspace`, an
+/// entry only needs to match a change in one of the packages, so stale entries
+/// are collected across packages and warned about once at the end.
+#[derive(Debug, Default)]
+pub struct StaleEntries {
+    /// `None` until the allowlist has been checked against a diff.
+    entries: Option<Vec<String>>,
+}
+
+impl StaleEntries {
+    /// Keeps only the entries that are also stale in `entries`, the stale
+    /// entries for the diff of one more package.
+    pub fn retain(&mut self, entries: impl Iterator<Item = impl Display>) {
+        let entries: Vec<String> = entries.map(|entry| entry.to_string()).collect();
+        match &mut self.entries {
+            Some(stale) => stale.retain(|entry| entries.contains(entry)),
+            None => self.entries = Some(entries),
+        }
+    }
+
+    pub fn warn(&self) {
+        for entry in self.entries.iter().flatten() {
+            eprintln!("Warning: Allowlist entry `{entry}` does not match any change");
+        }
+    }
+}
+
+impl Entry {
+    fn matches(&self, item: &PublicItem) -> bool {
+        if let Some(path) = &self.path {
+            // Entries with a path do not accept `impl`s of the type at that
+            // path, only the type itself
+            !matches!(item.kind(), Some(ItemKind::Impl(_))) && item.path().join("::") == *path
+        } else {
+            self.text
+                .as_ref()
+                .is_some_and(|text| *text == item.to_string())
+        }
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.path, &self.text) {
+            (Some(path), _) => write!(f, "path = {path:?}"),
+            (_, Some(text)) => write!(f, "text = {text:?}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_needs_path_or_text() {
+        let dir = tempfile::tempdir().unwrap();
+        for (toml, valid) in [
+            ("[[accepted]]\npath = \"a::b\"\nreason = \"x\"", true),
+            ("[[accepted]]\ntext = \"pub fn a::b()\"", true),
+            ("", true),
+            ("[[accepted]]\nreason = \"x\"", false),
+            (
+                "[[accepted]]\npath = \"a::b\"\ntext = \"pub fn a::b()\"",
+                false,
+            ),
+            ("[[accepted]]\npaht = \"a::b\"", false),
+        ] {
+            let path = dir.path().join("allowlist.toml");
+            std::fs::write(&path, toml).unwrap();
+            assert_eq!(Allowlist::from_file(&path).is_ok(), valid, "{toml}");
+        }
+    }
+}
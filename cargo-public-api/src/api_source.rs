@@ -1,16 +1,17 @@
 //! Contains various ways of obtaining the public API for crates.
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use rustdoc_json::BuildError;
 use std::path::{Path, PathBuf};
 
 use public_api::{MINIMUM_NIGHTLY_RUST_VERSION, PublicApi};
 
+use crate::worktree::Worktree;
 use crate::{Args, ArgsAndToolchain, Subcommand, git_utils};
 
 /// Represents some place from which a public API can be obtained.
 /// Examples: a published crate, a git commit, an existing file.
-pub trait ApiSource {
+pub trait ApiSource: Sync {
     /// Do the work necessary to obtain the public API.
     fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi>;
 
@@ -22,6 +23,19 @@ pub trait ApiSource {
         false
     }
 
+    /// If this source builds in a place of its own, e.g. a temporary git
+    /// worktree, so that it can build in parallel with other such sources.
+    fn builds_in_isolation(&self) -> bool {
+        false
+    }
+
+    /// If this source only knows the rendered text of each item, like a text
+    /// snapshot. Whoever diffs against such a source must reduce the other
+    /// public API to text too, to avoid spurious diffs from guessed tokens.
+    fn text_only(&self) -> bool {
+        false
+    }
+
     fn boxed(self) -> Box<dyn ApiSource>
     where
         Self: Sized + 'static,
@@ -35,7 +49,10 @@ pub struct CurrentDir;
 
 impl ApiSource for CurrentDir {
     fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi> {
-        public_api_for_current_dir(argst)
+        // The working tree is typically being edited, and can depend on files
+        // that git does not track, so it is never cached
+        let json_path = rustdoc_json_for_current_dir(argst)?;
+        public_api_from_rustdoc_json(json_path, &argst.args)
     }
 }
 /// The API is obtained from a crate published to crates.io. This struct only
@@ -61,9 +78,11 @@ impl ApiSource for PublishedCrate {
     }
 }
 
-/// The API is obtained from a git commit.
+/// The API is obtained from a git commit. Either by checking out the commit
+/// in place, or with `--worktree`, in a temporary git worktree.
 pub struct Commit {
     commit: String,
+    worktree: bool,
 }
 
 impl Commit {
@@ -71,22 +90,120 @@ impl Commit {
         Ok(Self {
             // Resolve the ref during creation to detect problems early
             commit: git_utils::resolve_ref(args.git_root()?, commit_ref)?,
+            worktree: args.diff_args().is_some_and(|a| a.worktree),
         })
     }
 }
 
 impl ApiSource for Commit {
     fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi> {
+        if self.worktree {
+            let worktree = Worktree::new(argst, &self.commit)?;
+            return public_api_for_checked_out_commit(&worktree.argst(argst)?);
+        }
+
         crate::git_checkout(&argst.args, &self.commit)?;
-        public_api_for_current_dir(argst)
+        // Without `--force`, changes in the working tree are kept when checking
+        // out, so whether the build can be cached is checked after checkout
+        public_api_for_checked_out_commit(argst)
     }
 
     fn changes_commit(&self) -> bool {
-        true
+        !self.worktree
+    }
+
+    fn builds_in_isolation(&self) -> bool {
+        self.worktree
     }
 }
 
-/// The API is obtained from an existing rustdoc JSON file.
+/// The API is obtained by building another crate on disk, e.g. a vendored copy
+/// or a fork that is not in the git history. The crate is either a directory
+/// with a `Cargo.toml`, or a `.crate` archive like the ones created by `cargo
+/// package`. It is built with the same features and target as the current
+/// crate.
+pub struct LocalCrate {
+    path: PathBuf,
+}
+
+impl LocalCrate {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// If `arg` refers to a crate directory or a `.crate` archive.
+    pub fn is_local_crate(arg: &str) -> bool {
+        let path = Path::new(arg);
+        path.join("Cargo.toml").is_file() || (is_crate_archive(path) && path.is_file())
+    }
+}
+
+impl ApiSource for LocalCrate {
+    fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi> {
+        let mut args = argst.args.clone();
+        args.manifest_path = if is_crate_archive(&self.path) {
+            unpack_crate_archive(&args, &self.path)?.join("Cargo.toml")
+        } else {
+            self.path.join("Cargo.toml")
+        };
+        let argst = ArgsAndToolchain {
+            args,
+            toolchain: argst.toolchain.clone(),
+        };
+        let json_path = rustdoc_json_for_current_dir(&argst)?;
+        public_api_from_rustdoc_json(json_path, &argst.args)
+    }
+}
+
+fn is_crate_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("crate"))
+}
+
+/// Unpacks the `.crate` archive at `archive`, which is a gzipped tarball with
+/// a single `name-version` directory, and returns the path to that directory.
+/// Like for published crates, the archive is unpacked in `args.target_dir` if
+/// set, so that tests do not interfere with each other.
+fn unpack_crate_archive(args: &Args, archive: &Path) -> Result<PathBuf> {
+    let mut unpack_dir = if let Some(target_dir) = &args.target_dir {
+        target_dir.clone()
+    } else {
+        dirs::cache_dir().unwrap_or_else(std::env::temp_dir)
+    };
+    unpack_dir.push("cargo-public-api");
+    unpack_dir.push("unpacked-crates");
+
+    let name = archive
+        .file_stem()
+        .ok_or_else(|| anyhow!("{archive:?} is not a `.crate` archive"))?;
+    let crate_dir = unpack_dir.join(name);
+    if crate_dir.exists() {
+        std::fs::remove_dir_all(&crate_dir)
+            .with_context(|| format!("Failed to remove old unpacked crate at {crate_dir:?}"))?;
+    }
+    std::fs::create_dir_all(&unpack_dir)?;
+
+    let status = std::process::Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(&unpack_dir)
+        .status()
+        .context("Failed to run `tar` to unpack the `.crate` archive")?;
+    if !status.success() {
+        bail!("Failed to unpack {archive:?}, see error message on stdout/stderr.");
+    }
+    if !crate_dir.join("Cargo.toml").is_file() {
+        bail!(
+            "Expected {archive:?} to contain a crate in {name:?}, like archives from `cargo package`"
+        );
+    }
+    Ok(crate_dir)
+}
+
+/// The API is obtained from an existing rustdoc JSON file, or from a structured
+/// snapshot, i.e. a [`PublicApi`] serialized to JSON with the `serde` feature
+/// of `public-api`.
 pub struct RustdocJson {
     path: PathBuf,
 }
@@ -99,20 +216,104 @@ impl RustdocJson {
 
 impl ApiSource for RustdocJson {
     fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi> {
+        if let Some(public_api) = structured_snapshot(&self.path)? {
+            return apply_filters(public_api, &self.path, &argst.args);
+        }
         public_api_from_rustdoc_json(&self.path, &argst.args)
     }
 }
 
-/// Builds the public API for the library in the current working directory. Note
-/// that we sometimes checkout a different commit before invoking this function,
-/// which means it will return the public API of that commit.
-fn public_api_for_current_dir(argst: &ArgsAndToolchain) -> Result<PublicApi> {
-    let json_path = rustdoc_json_for_current_dir(argst)?;
+/// The API is obtained from a text snapshot, like the ones written by
+/// `PublicApi::assert_eq_or_update()` of `public-api`.
+pub struct TextSnapshot {
+    path: PathBuf,
+}
+
+impl TextSnapshot {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl ApiSource for TextSnapshot {
+    fn obtain_api(&self, argst: &ArgsAndToolchain) -> Result<PublicApi> {
+        let snapshot = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read snapshot at {:?}", self.path))?;
+        apply_filters(
+            PublicApi::from_text_snapshot(&snapshot),
+            &self.path,
+            &argst.args,
+        )
+    }
+
+    fn text_only(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the structured snapshot at `path`, or `None` if the file is rustdoc
+/// JSON. Only rustdoc JSON has a top-level `format_version`, so the formats can
+/// be told apart without deserializing everything.
+fn structured_snapshot(path: &Path) -> Result<Option<PublicApi>> {
+    #[derive(serde::Deserialize)]
+    struct Format {
+        format_version: Option<serde::de::IgnoredAny>,
+    }
+
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read JSON at {path:?}"))?;
+    let format: Format =
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse JSON at {path:?}"))?;
+    if format.format_version.is_some() {
+        return Ok(None);
+    }
+    let public_api = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse structured snapshot at {path:?}"))?;
+    Ok(Some(public_api))
+}
+
+/// Applies args like `--omit` and `--only-path` to the public API of a
+/// snapshot, since the snapshot might have been written without them.
+fn apply_filters(public_api: PublicApi, snapshot: &Path, args: &Args) -> Result<PublicApi> {
+    public_api_builder_from_args(snapshot, args)?
+        .apply_filters(public_api)
+        .with_context(|| format!("Failed to filter the snapshot at {snapshot:?}"))
+}
+
+/// Builds the public API for the library in the current working directory,
+/// after a commit has been checked out there. The rustdoc JSON is cached if
+/// the working tree has no changes compared to the commit.
+fn public_api_for_checked_out_commit(argst: &ArgsAndToolchain) -> Result<PublicApi> {
+    let json_path = match clean_commit_source(&argst.args) {
+        Some(source) => {
+            crate::cache::rustdoc_json(argst, &source, || rustdoc_json_for_current_dir(argst))?
+        }
+        None => rustdoc_json_for_current_dir(argst)?,
+    };
     public_api_from_rustdoc_json(json_path, &argst.args)
 }
 
+/// Describes the package at the current commit for the rustdoc JSON cache, or
+/// returns `None` if there are changes or untracked files, since the rustdoc
+/// JSON then depends on more than the commit.
+fn clean_commit_source(args: &Args) -> Option<String> {
+    let git_root = args.git_root().ok()?;
+    if !git_utils::is_clean(&git_root).ok()? {
+        return None;
+    }
+    let commit = git_utils::resolve_ref(&git_root, "HEAD").ok()?;
+    // The commit says what the repo looks like, so the path within the repo is
+    // enough. This makes builds in temporary worktrees cacheable too.
+    let manifest_path = std::fs::canonicalize(&args.manifest_path).ok()?;
+    let manifest_path = manifest_path.strip_prefix(&git_root).ok()?;
+    Some(format!(
+        "{:?} of {manifest_path:?} at commit {commit}",
+        args.package
+    ))
+}
+
 /// Builds the rustdoc JSON for the library in the current working directory.
-/// Also see [`public_api_for_current_dir()`].
+/// Also see [`public_api_for_checked_out_commit()`].
 fn rustdoc_json_for_current_dir(argst: &ArgsAndToolchain) -> Result<PathBuf> {
     let builder = builder_from_args(argst);
     build_rustdoc_json(builder)
@@ -127,13 +328,20 @@ pub fn build_rustdoc_json(builder: rustdoc_json::Builder) -> Result<PathBuf> {
     }
 }
 
-fn public_api_builder_from_args(rustdoc_json: &Path, args: &Args) -> public_api::Builder {
-    public_api::Builder::from_rustdoc_json(rustdoc_json)
+fn public_api_builder_from_args(rustdoc_json: &Path, args: &Args) -> Result<public_api::Builder> {
+    Ok(public_api::Builder::from_rustdoc_json(rustdoc_json)
         .debug_sorting(args.debug_sorting)
         .omit_blanket_impls(args.omit_blanket_impls())
         .omit_auto_trait_impls(args.omit_auto_trait_impls())
         .omit_auto_derived_impls(args.omit_auto_derived_impls())
+        .omit_doc_hidden(args.omit_doc_hidden())
+        .omit_unstable(args.omit_unstable())
+        .unstable_features(&args.unstable_feature)
         .include_function_parameter_names(args.include_function_parameter_names())
+        .only_paths(&args.only_path)
+        .exclude_paths(&args.exclude_path)
+        .collapse_reexports(args.collapse_reexports)
+        .query(args.query()?))
 }
 
 /// Creates a rustdoc JSON builder based on the args to this program.
@@ -143,7 +351,9 @@ pub fn builder_from_args(argst: &ArgsAndToolchain) -> rustdoc_json::Builder {
         .manifest_path(&args.manifest_path)
         .all_features(args.all_features)
         .no_default_features(args.no_default_features)
-        .features(&args.features);
+        // Items behind unstable features must be built to be listed as unstable
+        .features(args.features.iter().chain(&args.unstable_feature))
+        .document_hidden_items(args.include_doc_hidden());
     if let Some(toolchain) = &argst.toolchain {
         builder = builder.toolchain(toolchain);
     }
@@ -173,7 +383,7 @@ fn public_api_from_rustdoc_json(path: impl AsRef<Path>, args: &Args) -> Result<P
         println!("Processing {json_path:?}");
     }
 
-    let public_api = public_api_builder_from_args(json_path, args)
+    let public_api = public_api_builder_from_args(json_path, args)?
         .build()
         .with_context(|| {
             format!(
@@ -211,9 +421,11 @@ fn virtual_manifest_error(manifest_path: &Path) -> Result<PathBuf> {
     Err(anyhow!(
         "`{:?}` is a virtual manifest.
 
-Listing or diffing the public API of an entire workspace is not supported.
+To list or diff the public API of all library packages in the workspace, use
+
+    cargo public-api --workspace
 
-Try
+or pick a specific package with
 
     cargo public-api -p specific-crate
 ",
@@ -54,6 +54,21 @@ impl Color {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum OutputFormat {
+    /// Human readable text.
+    Plain,
+
+    /// JSON with a versioned format, for consumption by other tools. See
+    /// `cargo-public-api/src/json.rs` for a description of the format.
+    Json,
+
+    /// GitHub flavored Markdown, e.g. for posting the diff as a comment on a
+    /// pull request. Diffs are grouped into collapsible sections.
+    Markdown,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
 #[value(rename_all = "kebab-case")]
 #[allow(clippy::enum_variant_names)] // We might add support for omitting other things in the future
@@ -70,6 +85,16 @@ pub enum Omit {
     /// Omit items that belong to Auto Derived Implementations such as `Clone`,
     /// `Debug`, and `Eq`.
     AutoDerivedImpls,
+
+    /// Omit items that are `#[doc(hidden)]`, and items inside such items. For
+    /// example support code for macros in a `#[doc(hidden)] pub mod
+    /// __private`.
+    DocHidden,
+
+    /// Omit items that are `#[unstable]`, or only available with any of the
+    /// `--unstable-feature`s, and items inside such items. What remains is the
+    /// stable public API.
+    Unstable,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
@@ -79,6 +104,11 @@ pub enum Include {
     /// default to avoid spurious API diffs when parameter names change. But
     /// they can sometimes be helpful to include in the output.
     FunctionParameterNames,
+
+    /// Include `#[doc(hidden)]` items, such as support code for macros. They
+    /// are annotated with `#[doc(hidden)]`, and can be omitted again with
+    /// `--omit doc-hidden` when diffing a crate that exposes some of them.
+    DocHidden,
 }
 
 #[cfg(test)]
@@ -0,0 +1,100 @@
+//! A persistent cache of rustdoc JSON. Building rustdoc JSON for a commit or a
+//! published version gives the same result every time, as long as the
+//! features, target and toolchain are the same, so we only build it once.
+//!
+//! Rustdoc JSON is cached rather than the public API, so that args like
+//! `--omit` apply to cached builds too.
+
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result};
+
+use crate::ArgsAndToolchain;
+
+/// Returns the path to cached rustdoc JSON for `source`, e.g. `example-api
+/// 0.1.0 from crates.io`, built with the features, target and toolchain of
+/// `argst`. On a cache miss, `build` is called to build it, and the result is
+/// added to the cache.
+pub fn rustdoc_json(
+    argst: &ArgsAndToolchain,
+    source: &str,
+    build: impl FnOnce() -> Result<PathBuf>,
+) -> Result<PathBuf> {
+    let Some(key) = key(argst, source) else {
+        return build();
+    };
+    let cached = cache_dir(argst).join(format!("{key}.json"));
+    if cached.exists() {
+        if argst.args.debug_processing {
+            eprintln!("Using cached rustdoc JSON {cached:?} for {source}");
+        }
+        return Ok(cached);
+    }
+
+    let built = build()?;
+    add_to_cache(&built, &cached)
+        .with_context(|| format!("Failed to cache rustdoc JSON at {cached:?}"))?;
+    Ok(built)
+}
+
+/// The cache key, or `None` if nothing should be cached. The key must change
+/// whenever the rustdoc JSON could be different.
+fn key(argst: &ArgsAndToolchain, source: &str) -> Option<String> {
+    let args = &argst.args;
+    if args.no_cache {
+        return None;
+    }
+    // Toolchains like `nightly` change over time, so use the actual version
+    let rustc_version = crate::toolchain::rustc_version(argst.toolchain.as_deref())?;
+
+    let mut features = args.features.clone();
+    features.extend(args.unstable_feature.iter().cloned());
+    features.sort();
+    features.dedup();
+
+    let key = format!(
+        "{source}\n{features:?}\n{}\n{}\n{:?}\n{}\n{rustc_version}",
+        args.all_features,
+        args.no_default_features,
+        args.target,
+        args.include_doc_hidden(),
+    );
+    let mut hasher = rustc_hash::FxHasher::default();
+    hasher.write(key.as_bytes());
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+/// Like for published crates, tests set `args.target_dir` to a temporary dir,
+/// so that they do not share a cache.
+fn cache_dir(argst: &ArgsAndToolchain) -> PathBuf {
+    let mut cache_dir = if let Some(target_dir) = &argst.args.target_dir {
+        target_dir.clone()
+    } else {
+        dirs::cache_dir().unwrap_or_else(std::env::temp_dir)
+    };
+
+    cache_dir.push("cargo-public-api");
+    cache_dir.push("rustdoc-json");
+    cache_dir
+}
+
+/// Copies to a temporary file first and then renames it, so that concurrent
+/// runs never see a partially written file. The temporary file is unique per
+/// call, since e.g. the two sides of a `diff --worktree` are cached in
+/// parallel.
+fn add_to_cache(built: &Path, cached: &Path) -> Result<()> {
+    static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = cached.parent().expect("cached files are in a dir");
+    std::fs::create_dir_all(dir)?;
+    let temp = dir.join(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::copy(built, &temp)?;
+    std::fs::rename(&temp, cached)?;
+    Ok(())
+}
@@ -4,6 +4,14 @@ use public_api::{PublicItem, diff::ChangedPublicItem};
 pub enum Error {
     #[error("The API diff is not allowed as per --deny: {0}")]
     DiffDenied(Violations),
+
+    #[error(
+        "The version in Cargo.toml ({actual}) is lower than the required version ({required}) as per --deny-insufficient-version"
+    )]
+    InsufficientVersion {
+        required: semver::Version,
+        actual: semver::Version,
+    },
 }
 
 #[derive(Debug)]
@@ -0,0 +1,313 @@
+//! Lists or diffs the public API for several sets of cargo features at once.
+//! `--feature-matrix` annotates each listed item with the features it
+//! requires, and `diff --feature-powerset` and `diff --feature-set` report
+//! which feature sets each change affects.
+
+use anyhow::{Context, Result, anyhow, bail};
+use public_api::PublicApi;
+use public_api::diff::PublicApiDiff;
+
+use crate::api_source::ApiSource;
+use crate::merged_api::MergedApi;
+use crate::merged_diff::MergedDiff;
+use crate::{Args, ArgsAndToolchain};
+
+/// A set of cargo features to build rustdoc JSON with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeatureSet {
+    /// The default features, like a plain `cargo build`.
+    Default,
+
+    /// `--no-default-features`
+    None,
+
+    /// `--all-features`
+    All,
+
+    /// `--no-default-features --features <features>`
+    Features(Vec<String>),
+}
+
+impl std::fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default features"),
+            Self::None => write!(f, "no features"),
+            Self::All => write!(f, "all features"),
+            Self::Features(features) if features.len() == 1 => {
+                write!(f, "only feature `{}`", features[0])
+            }
+            Self::Features(features) => write!(f, "only features `{}`", features.join("`, `")),
+        }
+    }
+}
+
+impl FeatureSet {
+    fn only_feature(&self) -> Option<&String> {
+        match self {
+            Self::Features(features) if features.len() == 1 => features.first(),
+            _ => None,
+        }
+    }
+}
+
+impl ArgsAndToolchain {
+    /// The args to use to build with `feature_set`, regardless of any
+    /// feature args given by the user.
+    pub fn with_feature_set(&self, feature_set: &FeatureSet) -> Self {
+        let mut args = self.args.clone();
+        args.all_features = *feature_set == FeatureSet::All;
+        args.no_default_features =
+            matches!(feature_set, FeatureSet::None | FeatureSet::Features(_));
+        args.features = match feature_set {
+            FeatureSet::Features(features) => features.clone(),
+            _ => vec![],
+        };
+        Self {
+            args,
+            toolchain: self.toolchain.clone(),
+        }
+    }
+}
+
+/// What features an item requires, given in which [`FeatureSet`]s it is part
+/// of the public API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Requirement {
+    /// The item is part of the public API even without default features.
+    Nothing,
+
+    /// Any one of the features is enough to enable the item.
+    AnyOf(Vec<String>),
+
+    /// The item requires some combination of features, and is only part of the
+    /// public API with all features enabled.
+    AllOf(Vec<String>),
+}
+
+impl Requirement {
+    fn new(feature_sets: &[FeatureSet], present_in: &[usize]) -> Self {
+        let present: Vec<&FeatureSet> = present_in.iter().map(|&i| &feature_sets[i]).collect();
+        let only: Vec<String> = present
+            .iter()
+            .filter_map(|set| set.only_feature().cloned())
+            .collect();
+
+        if present.contains(&&FeatureSet::None) {
+            Self::Nothing
+        } else if !only.is_empty() {
+            Self::AnyOf(only)
+        } else if present.contains(&&FeatureSet::Default) {
+            Self::AnyOf(vec!["default".to_owned()])
+        } else {
+            Self::AllOf(
+                feature_sets
+                    .iter()
+                    .filter_map(|set| set.only_feature().cloned())
+                    .collect(),
+            )
+        }
+    }
+
+    /// The requirement as a `cfg` condition like `feature = "std"`, or `None`
+    /// if the item requires no features.
+    pub fn cfg(&self) -> Option<String> {
+        let features = |features: &[String]| {
+            features
+                .iter()
+                .map(|feature| format!("feature = {feature:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Self::Nothing => None,
+            Self::AnyOf(only) if only.len() == 1 => Some(features(only)),
+            Self::AnyOf(any) => Some(format!("any({})", features(any))),
+            Self::AllOf(all) => Some(format!("all({})", features(all))),
+        }
+    }
+}
+
+/// The feature sets to build the package with: default features, no features,
+/// all features, and each feature on its own.
+pub fn feature_sets(args: &Args) -> Result<Vec<FeatureSet>> {
+    let mut feature_sets = vec![FeatureSet::Default, FeatureSet::None, FeatureSet::All];
+    feature_sets.extend(
+        package_features(args)?
+            .into_iter()
+            .map(|feature| FeatureSet::Features(vec![feature])),
+    );
+    Ok(feature_sets)
+}
+
+/// The feature sets for `diff --feature-powerset`: default features, and
+/// every combination of the other features, including none of them.
+pub fn feature_powerset(args: &Args) -> Result<Vec<FeatureSet>> {
+    let features = package_features(args)?;
+    if features.len() > MAX_POWERSET_FEATURES {
+        bail!(
+            "The package has {} features, which is too many for `--feature-powerset`. Use `--feature-set` to pick the feature sets to diff",
+            features.len()
+        );
+    }
+
+    let mut feature_sets = vec![FeatureSet::Default, FeatureSet::None];
+    for subset in 1..(1_usize << features.len()) {
+        feature_sets.push(FeatureSet::Features(
+            (features.iter().enumerate())
+                .filter(|(index, _)| subset & (1 << index) != 0)
+                .map(|(_, feature)| feature.clone())
+                .collect(),
+        ));
+    }
+    Ok(feature_sets)
+}
+
+/// Parses a `--feature-set` value: a comma separated list of features to
+/// build with instead of the default features. `default` means the default
+/// features, and an empty value means no features.
+pub fn parse_feature_set(value: &str) -> FeatureSet {
+    let features: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|feature| !feature.is_empty())
+        .map(str::to_owned)
+        .collect();
+    match features.as_slice() {
+        [] => FeatureSet::None,
+        [feature] if feature == "default" => FeatureSet::Default,
+        _ => FeatureSet::Features(features),
+    }
+}
+
+/// Each feature set doubles the number of builds, so this is already a lot.
+const MAX_POWERSET_FEATURES: usize = 6;
+
+/// The features of the package, except `default`. Features are read from
+/// `Cargo.toml` with `cargo metadata`.
+fn package_features(args: &Args) -> Result<Vec<String>> {
+    let package_name = crate::published_crate::package_name(args)?;
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(&args.manifest_path)
+        .no_deps()
+        .exec()
+        .with_context(|| {
+            format!(
+                "Failed to run `cargo metadata` for {:?}",
+                args.manifest_path
+            )
+        })?;
+    let package = metadata
+        .packages
+        .into_iter()
+        .find(|package| package.name.as_str() == package_name)
+        .ok_or_else(|| anyhow!("Could not find package `{package_name}` in the workspace"))?;
+
+    // `features` is a `BTreeMap`, so the order is stable
+    Ok(package
+        .features
+        .into_keys()
+        .filter(|feature| feature != "default")
+        .collect())
+}
+
+/// Builds the public API of `api` with each of the `feature_sets`, and merges
+/// them. Each item is returned with the features it requires.
+pub fn public_api_with_required_features(
+    argst: &ArgsAndToolchain,
+    api: &dyn ApiSource,
+    feature_sets: &[FeatureSet],
+) -> Result<Vec<(public_api::PublicItem, Requirement)>> {
+    let apis = feature_sets
+        .iter()
+        .map(|feature_set| {
+            api.obtain_api(&argst.with_feature_set(feature_set))
+                .with_context(|| format!("Failed to build the public API with {feature_set}"))
+        })
+        .collect::<Result<Vec<PublicApi>>>()?;
+
+    Ok(MergedApi::new(apis)
+        .items
+        .into_iter()
+        .map(|merged| {
+            let requirement = Requirement::new(feature_sets, &merged.present_in);
+            (merged.item, requirement)
+        })
+        .collect())
+}
+
+/// Diffs `old` and `new` with each of the `feature_sets`, and merges the
+/// diffs.
+pub fn diff_with_feature_sets(
+    argst: &ArgsAndToolchain,
+    old: &dyn ApiSource,
+    new: &dyn ApiSource,
+    feature_sets: &[FeatureSet],
+) -> Result<MergedDiff> {
+    let diffs = feature_sets
+        .iter()
+        .map(|feature_set| {
+            crate::diff_apis(&argst.with_feature_set(feature_set), old, new)
+                .with_context(|| format!("Failed to diff the public API with {feature_set}"))
+        })
+        .collect::<Result<Vec<PublicApiDiff>>>()?;
+
+    Ok(MergedDiff::new(diffs))
+}
+
+/// Describes the feature sets of `present_in`, e.g. `default features; no
+/// features`.
+pub fn describe(feature_sets: &[FeatureSet], present_in: &[usize]) -> String {
+    if present_in.len() == feature_sets.len() && feature_sets.len() > 1 {
+        return "all feature sets".to_owned();
+    }
+    present_in
+        .iter()
+        .map(|&index| feature_sets[index].to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requirement() {
+        let feature_sets = [
+            FeatureSet::Default,
+            FeatureSet::None,
+            FeatureSet::All,
+            FeatureSet::Features(vec!["a".to_owned()]),
+            FeatureSet::Features(vec!["b".to_owned()]),
+        ];
+        let requirement = |present_in: &[usize]| {
+            Requirement::new(&feature_sets, present_in)
+                .cfg()
+                .unwrap_or_default()
+        };
+
+        assert_eq!(requirement(&[0, 1, 2, 3, 4]), "");
+        assert_eq!(requirement(&[0, 2, 3]), r#"feature = "a""#);
+        assert_eq!(
+            requirement(&[2, 3, 4]),
+            r#"any(feature = "a", feature = "b")"#
+        );
+        assert_eq!(requirement(&[0, 2]), r#"feature = "default""#);
+        assert_eq!(requirement(&[2]), r#"all(feature = "a", feature = "b")"#);
+    }
+
+    #[test]
+    fn parse_feature_sets() {
+        assert_eq!(parse_feature_set(""), FeatureSet::None);
+        assert_eq!(parse_feature_set("default"), FeatureSet::Default);
+        assert_eq!(
+            parse_feature_set("a, b"),
+            FeatureSet::Features(vec!["a".to_owned(), "b".to_owned()])
+        );
+        assert_eq!(
+            parse_feature_set("a,b").to_string(),
+            "only features `a`, `b`"
+        );
+    }
+}
@@ -28,6 +28,54 @@ pub fn git_checkout(git_root: &Path, commit: &str, quiet: bool, force: bool) ->
     }
 }
 
+/// Synchronously do a `git worktree add` of `commit` at `dir`, with a detached
+/// `HEAD`.
+pub fn git_worktree_add(git_root: &Path, dir: &Path, commit: &str, quiet: bool) -> Result<()> {
+    let mut command = Command::new("git");
+    command.current_dir(git_root);
+    command.args(["worktree", "add", "--detach"]);
+    if quiet {
+        command.arg("--quiet");
+    }
+    command.arg(dir).arg(commit);
+    if command.spawn()?.wait()?.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to `git worktree add {:?} {}`, see error message on stdout/stderr.",
+            dir,
+            commit,
+        ))
+    }
+}
+
+/// Synchronously do a `git worktree remove` of the worktree at `dir`, even if
+/// it has changes such as an untracked `Cargo.lock`.
+pub fn git_worktree_remove(git_root: &Path, dir: &Path) -> Result<()> {
+    let mut command = Command::new("git");
+    command.current_dir(git_root);
+    command.args(["worktree", "remove", "--force"]).arg(dir);
+    trimmed_stdout(command).map(|_| ())
+}
+
+/// Synchronously do a `git submodule update --init --recursive` in `dir`.
+pub fn git_submodule_update_init(dir: &Path, quiet: bool) -> Result<()> {
+    let mut command = Command::new("git");
+    command.current_dir(dir);
+    command.args(["submodule", "update", "--init", "--recursive"]);
+    if quiet {
+        command.arg("--quiet");
+    }
+    if command.spawn()?.wait()?.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to `git submodule update --init --recursive` in {:?}, see error message on stdout/stderr.",
+            dir,
+        ))
+    }
+}
+
 /// Goes up the chain of parents and looks for a `.git` dir.
 pub fn git_root_from_manifest_path(manifest_path: &Path) -> Result<PathBuf> {
     let err_fn = || anyhow!("No `.git` dir when starting from `{:?}`.", &manifest_path);
@@ -67,6 +115,12 @@ pub fn current_commit(path: impl AsRef<Path>) -> Result<String> {
     trimmed_git_stdout(path, &["rev-parse", "--short", "HEAD"])
 }
 
+/// Returns `true` if there are no changes to tracked files and no untracked
+/// files. Files ignored by `.gitignore` do not count.
+pub fn is_clean(path: impl AsRef<Path>) -> Result<bool> {
+    Ok(trimmed_git_stdout(path, &["status", "--porcelain"])?.is_empty())
+}
+
 fn trimmed_git_stdout(path: impl AsRef<Path>, args: &[&str]) -> Result<String> {
     let mut git = Command::new("git");
     git.current_dir(path);
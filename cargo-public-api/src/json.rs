@@ -0,0 +1,425 @@
+//! Machine readable output for `--output-format json`.
+//!
+//! The format is versioned via the top level `format_version` field. Adding
+//! fields is not considered a breaking change, but removing or changing the
+//! meaning of a field is, and requires `FORMAT_VERSION` to be bumped.
+//!
+//! Listing the public API prints
+//!
+//! ```json
+//! { "format_version": 1, "items": [ <item>, ... ] }
+//! ```
+//!
+//! and diffing prints
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "removed": [ <item>, ... ],
+//!   "changed": [ { "old": <item>, "new": <item> }, ... ],
+//!   "deprecated": [ { "old": <item>, "new": <item> }, ... ],
+//!   "moved": [ { "old": <item>, "new": <item> }, ... ],
+//!   "added": [ <item>, ... ]
+//! }
+//! ```
+//!
+//! where each `<item>` looks like
+//!
+//! ```json
+//! {
+//!   "text": "pub fn example_api::function(v1_param: example_api::Struct)",
+//!   "path": ["example_api", "function"],
+//!   "kind": "function",
+//!   "unstable": false,
+//!   "doc_hidden": false,
+//!   "deprecated": false,
+//!   "canonical_path": "example_api::function",
+//!   "reexport": false,
+//!   "reexport_paths": [],
+//!   "tokens": [ { "kind": "qualifier", "text": "pub" }, ... ]
+//! }
+//! ```
+//!
+//! With `--feature-matrix`, each listed item also has a `cfg` field with the
+//! features the item requires, e.g. `"feature = \"std\""`, or `null` if it
+//! requires none. With `--targets`, `cfg` is instead the targets the item is
+//! part of the public API on, e.g. `"target = \"x86_64-pc-windows-msvc\""`,
+//! or `null` if it is part of it on all targets.
+//!
+//! With `diff --feature-powerset` or `diff --feature-set`, changes are grouped
+//! by the feature sets they affect, and each group is a diff like above but
+//! with a `feature_sets` field instead of `format_version`:
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "diffs": [ { "feature_sets": ["default features", "no features"], "removed": [ ... ], ... }, ... ]
+//! }
+//! ```
+//!
+//! With `diff --targets`, groups have a `targets` field with target triples
+//! instead of `feature_sets`.
+//!
+//! With `--workspace`, the listing or diff of each package is wrapped like
+//! this, where `output` is `null` and `error` is set if the package failed:
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "packages": [ { "name": "some-crate", "output": <listing or diff>, "error": null }, ... ]
+//! }
+//! ```
+//!
+//! `stats` prints a count per label in each section, e.g.
+//!
+//! ```json
+//! {
+//!   "format_version": 1,
+//!   "summary": { "items": 42, "unsafe functions": 1, ... },
+//!   "kinds": { "function": 3, "struct": 2, ... },
+//!   "impls": { "trait_impl": 12, ... },
+//!   "modules": { "example_api": 40, ... }
+//! }
+//! ```
+//!
+//! where each count is instead `{ "old": 40, "new": 42, "delta": 2 }` when
+//! two public APIs are compared.
+//!
+//! `impl`s and their items have the `path` of the type or trait the `impl`
+//! belongs to. Rustdoc JSON item ids are left out on purpose, since they change
+//! between builds and so can not be stored or compared. `kind` is the kind of item in
+//! `snake_case`, e.g. `"struct_field"`, `"method"` or `"trait_impl"`, see
+//! `public_api::ItemKind`. When diffing against a text snapshot, `kind` of the
+//! old items is `null` and `path` of old `impl`s is empty. `unstable` is `true`
+//! for `#[unstable]` items and items only available with an
+//! `--unstable-feature`. `doc_hidden` is `true` for `#[doc(hidden)]` items and
+//! items inside them, which are only listed with `--include doc-hidden`.
+//! `deprecated` is `true` for `#[deprecated]` items.
+//!
+//! `canonical_path` is where the item is defined, e.g. `"a::c::B"` for both
+//! `a::c::B` and `a::B` after `pub use c::B;` in module `a`, and is empty when
+//! not known. `reexport` is `true` for items reached via such a re-export
+//! alias, like `a::B`. With `--collapse-reexports`, aliases are omitted and
+//! `reexport_paths` lists them for the item at the canonical path instead,
+//! e.g. `["a::B"]`.
+
+use std::io::{Result, Write};
+
+use public_api::{
+    PublicItem,
+    diff::{ChangedPublicItem, PublicApiDiff},
+    tokens::Token,
+};
+use serde::{Serialize, ser::SerializeMap};
+
+use crate::stats::{Row, Section};
+
+/// Bump this when making a breaking change to the format. See module docs.
+const FORMAT_VERSION: u32 = 1;
+
+pub struct Json;
+
+impl Json {
+    pub fn print_items<'a>(
+        w: &mut dyn Write,
+        items: impl Iterator<Item = &'a PublicItem>,
+    ) -> Result<()> {
+        print(
+            w,
+            &JsonList {
+                format_version: FORMAT_VERSION,
+                items: items.map(JsonItem::from).collect(),
+            },
+        )
+    }
+
+    pub fn print_items_with_cfg<'a>(
+        w: &mut dyn Write,
+        items: impl Iterator<Item = (&'a PublicItem, Option<String>)>,
+    ) -> Result<()> {
+        print(
+            w,
+            &JsonListWithCfg {
+                format_version: FORMAT_VERSION,
+                items: items
+                    .map(|(item, cfg)| JsonItemWithCfg {
+                        item: JsonItem::from(item),
+                        cfg,
+                    })
+                    .collect(),
+            },
+        )
+    }
+
+    pub fn print_workspace(w: &mut dyn Write, packages: Vec<JsonPackage>) -> Result<()> {
+        print(
+            w,
+            &JsonWorkspace {
+                format_version: FORMAT_VERSION,
+                packages,
+            },
+        )
+    }
+
+    pub fn print_diff(w: &mut dyn Write, diff: &PublicApiDiff) -> Result<()> {
+        print(
+            w,
+            &JsonDiff {
+                format_version: FORMAT_VERSION,
+                changes: JsonChanges::from(diff),
+            },
+        )
+    }
+
+    /// Prints several diffs, each with the configurations it is for.
+    pub fn print_diffs<'a>(
+        w: &mut dyn Write,
+        diffs: impl Iterator<Item = (Configurations, &'a PublicApiDiff)>,
+    ) -> Result<()> {
+        print(
+            w,
+            &JsonDiffs {
+                format_version: FORMAT_VERSION,
+                diffs: diffs
+                    .map(|(configurations, diff)| JsonConfigurationsDiff {
+                        configurations,
+                        changes: JsonChanges::from(diff),
+                    })
+                    .collect(),
+            },
+        )
+    }
+
+    /// Prints each section of stats as an object with a count per label, or
+    /// with old and new counts and their difference when two public APIs are
+    /// compared.
+    pub fn print_stats(w: &mut dyn Write, sections: &[Section]) -> Result<()> {
+        print(
+            w,
+            &JsonStats {
+                format_version: FORMAT_VERSION,
+                sections: JsonSections(sections),
+            },
+        )
+    }
+}
+
+fn print(w: &mut dyn Write, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer_pretty(&mut *w, value)?;
+    writeln!(w)
+}
+
+#[derive(Serialize)]
+struct JsonList<'a> {
+    format_version: u32,
+    items: Vec<JsonItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonListWithCfg<'a> {
+    format_version: u32,
+    items: Vec<JsonItemWithCfg<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonItemWithCfg<'a> {
+    #[serde(flatten)]
+    item: JsonItem<'a>,
+    cfg: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonStats<'a> {
+    format_version: u32,
+    #[serde(flatten)]
+    sections: JsonSections<'a>,
+}
+
+/// Serializes each section as a field, with rows in the order of the section.
+struct JsonSections<'a>(&'a [Section]);
+
+impl Serialize for JsonSections<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for section in self.0 {
+            map.serialize_entry(section.key, &JsonRows(&section.rows))?;
+        }
+        map.end()
+    }
+}
+
+struct JsonRows<'a>(&'a [Row]);
+
+impl Serialize for JsonRows<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for row in self.0 {
+            match (row.old, row.delta()) {
+                (Some(old), Some(delta)) => map.serialize_entry(
+                    &row.label,
+                    &JsonCountDelta {
+                        old,
+                        new: row.new,
+                        delta,
+                    },
+                )?,
+                _ => map.serialize_entry(&row.label, &row.new)?,
+            }
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonCountDelta {
+    old: usize,
+    new: usize,
+    delta: i64,
+}
+
+#[derive(Serialize)]
+struct JsonDiff<'a> {
+    format_version: u32,
+    #[serde(flatten)]
+    changes: JsonChanges<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonDiffs<'a> {
+    format_version: u32,
+    diffs: Vec<JsonConfigurationsDiff<'a>>,
+}
+
+/// The build configurations that a diff printed by [`Json::print_diffs`] is
+/// for, e.g. `"feature_sets": ["default features"]`.
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Configurations {
+    FeatureSets(Vec<String>),
+    Targets(Vec<String>),
+}
+
+#[derive(Serialize)]
+struct JsonConfigurationsDiff<'a> {
+    #[serde(flatten)]
+    configurations: Configurations,
+    #[serde(flatten)]
+    changes: JsonChanges<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonChanges<'a> {
+    removed: Vec<JsonItem<'a>>,
+    changed: Vec<JsonChangedItem<'a>>,
+    deprecated: Vec<JsonChangedItem<'a>>,
+    moved: Vec<JsonChangedItem<'a>>,
+    added: Vec<JsonItem<'a>>,
+}
+
+impl<'a> From<&'a PublicApiDiff> for JsonChanges<'a> {
+    fn from(diff: &'a PublicApiDiff) -> Self {
+        Self {
+            removed: diff.removed.iter().map(JsonItem::from).collect(),
+            changed: diff.changed.iter().map(JsonChangedItem::from).collect(),
+            deprecated: diff.deprecated.iter().map(JsonChangedItem::from).collect(),
+            moved: diff.moved.iter().map(JsonChangedItem::from).collect(),
+            added: diff.added.iter().map(JsonItem::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonWorkspace {
+    format_version: u32,
+    packages: Vec<JsonPackage>,
+}
+
+/// The listing or diff of one package in a workspace.
+#[derive(Serialize)]
+pub struct JsonPackage {
+    pub name: String,
+    pub output: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonChangedItem<'a> {
+    old: JsonItem<'a>,
+    new: JsonItem<'a>,
+}
+
+impl<'a> From<&'a ChangedPublicItem> for JsonChangedItem<'a> {
+    fn from(changed: &'a ChangedPublicItem) -> Self {
+        Self {
+            old: JsonItem::from(&changed.old),
+            new: JsonItem::from(&changed.new),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonItem<'a> {
+    text: String,
+    path: &'a [String],
+    kind: Option<String>,
+    unstable: bool,
+    doc_hidden: bool,
+    deprecated: bool,
+    canonical_path: &'a str,
+    reexport: bool,
+    reexport_paths: &'a [String],
+    tokens: Vec<JsonToken<'a>>,
+}
+
+impl<'a> From<&'a PublicItem> for JsonItem<'a> {
+    fn from(item: &'a PublicItem) -> Self {
+        Self {
+            text: item.to_string(),
+            path: item.path(),
+            kind: item.kind().map(|kind| kind.to_string()),
+            unstable: item.is_unstable(),
+            doc_hidden: item.is_doc_hidden(),
+            deprecated: item.is_deprecated(),
+            canonical_path: item.canonical_path(),
+            reexport: item.is_reexport(),
+            reexport_paths: item.reexport_paths(),
+            tokens: item.tokens().map(JsonToken::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonToken<'a> {
+    kind: &'static str,
+    text: &'a str,
+}
+
+impl<'a> From<&'a Token> for JsonToken<'a> {
+    fn from(token: &'a Token) -> Self {
+        let kind = match token {
+            Token::Symbol(_) => "symbol",
+            Token::Qualifier(_) => "qualifier",
+            Token::Kind(_) => "kind",
+            Token::Whitespace => "whitespace",
+            Token::Identifier(_) => "identifier",
+            Token::Annotation(_) => "annotation",
+            Token::Self_(_) => "self",
+            Token::Function(_) => "function",
+            Token::Lifetime(_) => "lifetime",
+            Token::Keyword(_) => "keyword",
+            Token::Generic(_) => "generic",
+            Token::Primitive(_) => "primitive",
+            Token::Type(_) => "type",
+        };
+        Self {
+            kind,
+            text: token.text(),
+        }
+    }
+}
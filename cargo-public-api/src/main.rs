@@ -1,23 +1,42 @@
 use std::ffi::OsString;
-use std::io::{stderr, stdout};
+use std::io::{Write, stderr, stdout};
 use std::path::{Path, PathBuf};
 
+use allowlist::{Allowlist, StaleEntries};
 use anyhow::{Result, anyhow, bail};
-use api_source::{ApiSource, Commit, CurrentDir, PublishedCrate, RustdocJson};
-use arg_types::{Color, DenyMethod, Include, Omit};
+use api_source::{
+    ApiSource, Commit, CurrentDir, LocalCrate, PublishedCrate, RustdocJson, TextSnapshot,
+};
+use arg_types::{Color, DenyMethod, Include, Omit, OutputFormat};
 use git_utils::current_branch_or_commit;
+use json::Json;
+use markdown::Markdown;
+use merged_diff::MergedDiff;
 use plain::Plain;
+use public_api::PublicApi;
 use public_api::diff::PublicApiDiff;
 
 use clap::{CommandFactory, Parser};
 
+mod allowlist;
 mod api_source;
 mod arg_types;
+mod cache;
 mod error;
+mod feature_sets;
 mod git_utils;
+mod json;
+mod markdown;
+mod merged_api;
+mod merged_diff;
 mod plain;
 mod published_crate;
+mod stats;
+mod targets;
 mod toolchain;
+mod version_bump;
+mod workspace;
+mod worktree;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -28,6 +47,7 @@ mod toolchain;
     bin_name = "cargo public-api"
 )]
 #[command(flatten_help = true)]
+#[derive(Clone)]
 pub struct Args {
     /// Path to `Cargo.toml`.
     #[arg(global = true, long, value_name = "PATH", default_value = "Cargo.toml")]
@@ -37,6 +57,15 @@ pub struct Args {
     #[arg(global = true, long, short)]
     package: Option<String>,
 
+    /// List or diff the public API of all library packages in the workspace.
+    /// The output is grouped per package and followed by a summary.
+    #[arg(global = true, long, conflicts_with = "package")]
+    workspace: bool,
+
+    /// Exclude a package from `--workspace`. Can be used more than once.
+    #[arg(global = true, long, value_name = "PACKAGE", requires = "workspace")]
+    exclude: Vec<String>,
+
     /// Omit noisy items.
     #[arg(global = true, long, value_enum, value_delimiter = ',')]
     omit: Option<Vec<Omit>>,
@@ -52,6 +81,30 @@ pub struct Args {
     #[arg(global = true, short, long, action = clap::ArgAction::Count)]
     simplified: u8,
 
+    /// Only list or diff items with a path that matches the given glob
+    /// pattern, e.g. `my_crate::client::**`. In a pattern, `*` matches any part
+    /// of a path segment and `**` matches any number of path segments. Can be
+    /// used more than once.
+    #[arg(global = true, long, value_name = "GLOB")]
+    only_path: Vec<String>,
+
+    /// Omit items with a path that matches the given glob pattern, e.g.
+    /// `my_crate::__private::**`. Can be used more than once.
+    #[arg(global = true, long, value_name = "GLOB")]
+    exclude_path: Vec<String>,
+
+    /// Consider items that are only available with the given cargo feature,
+    /// e.g. `nightly`, to be unstable, like `#[unstable]` items. Use
+    /// `--omit unstable` to omit them. Can be used more than once.
+    #[arg(global = true, long, value_name = "FEATURE")]
+    unstable_feature: Vec<String>,
+
+    /// List items that are re-exported with `pub use` only once, at their
+    /// canonical path, followed by the paths they are re-exported at, e.g.
+    /// `pub struct my_crate::inner::Foo // re-exported at my_crate::Foo`.
+    #[arg(global = true, long)]
+    collapse_reexports: bool,
+
     /// Include extra details.
     #[arg(global = true, long, value_enum, value_delimiter = ',')]
     include: Option<Vec<Include>>,
@@ -77,10 +130,33 @@ pub struct Args {
     #[arg(global = true, long)]
     no_default_features: bool,
 
+    /// List the public API with default features, with no features, with all
+    /// features, and with each feature on its own, and annotate each item with
+    /// the features it requires, e.g. `#[cfg(feature = "std")]`.
+    #[arg(
+        long,
+        conflicts_with_all = ["features", "all_features", "no_default_features", "targets"]
+    )]
+    feature_matrix: bool,
+
     /// Build for the target triple
     #[arg(global = true, long)]
     target: Option<String>,
 
+    /// Build for each of the given comma separated target triples, and
+    /// annotate listed items that are only part of the public API on some of
+    /// them, e.g. `#[cfg(target = "x86_64-pc-windows-msvc")]`. When diffing,
+    /// report which targets each change affects. The targets must be
+    /// installed, e.g. with `rustup target add`.
+    #[arg(
+        global = true,
+        long,
+        value_name = "TARGETS",
+        value_delimiter = ',',
+        conflicts_with = "target"
+    )]
+    targets: Vec<String>,
+
     /// When to color the output.
     ///
     /// By default, `--color=auto` is active. Using just `--color` without an
@@ -88,6 +164,10 @@ pub struct Args {
     #[arg(global = true, long, value_enum)]
     color: Option<Option<Color>>,
 
+    /// How to format the listed or diffed public API.
+    #[arg(global = true, long, value_enum, default_value_t = OutputFormat::Plain)]
+    output_format: OutputFormat,
+
     /// List the public API based on the given rustdoc JSON file.
     ///
     /// Example:
@@ -122,6 +202,13 @@ pub struct Args {
     #[arg(global = true, long, value_name = "PATH", hide = true)]
     target_dir: Option<PathBuf>,
 
+    /// Always build rustdoc JSON, and do not cache it. By default, rustdoc
+    /// JSON for published versions and commits is cached, keyed by version
+    /// or commit, features, target and toolchain. The working tree is never
+    /// cached.
+    #[arg(global = true, long)]
+    no_cache: bool,
+
     /// Forwarded to rustdoc JSON build command
     #[arg(global = true, long, hide = true)]
     cap_lints: Option<String>,
@@ -140,8 +227,23 @@ struct ArgsAndToolchain {
     toolchain: Option<String>,
 }
 
+/// The subcommand used for querying.
+#[derive(Parser, Debug, Clone)]
+struct QueryArgs {
+    /// Predicates like `kind:fn` or `returns:Result`, which items must all match.
+    #[arg(required = true, value_name = "PREDICATE")]
+    predicates: Vec<String>,
+}
+
+/// The subcommand used for summarizing.
+#[derive(Parser, Debug, Clone)]
+struct StatsArgs {
+    /// What to summarize. Same syntax as for `cargo public-api diff`.
+    args: Vec<String>,
+}
+
 /// The subcommand used for diffing.
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 struct DiffArgs {
     /// Exit with failure if the specified API diff is detected.
     ///
@@ -150,11 +252,70 @@ struct DiffArgs {
     #[arg(long, value_enum)]
     deny: Option<Vec<DenyMethod>>,
 
+    /// TOML file with changes to the public API that `--deny` shall accept.
+    /// Entries that do not match any change are reported, so that they can be
+    /// removed. See `cargo-public-api/src/allowlist.rs` for the format.
+    #[arg(long, value_name = "PATH", requires = "deny")]
+    allowlist: Option<PathBuf>,
+
     /// Force the diff. For example, when diffing commits, enabling this option
     /// will discard working tree changes during git checkouts of other commits.
     #[arg(long)]
     force: bool,
 
+    /// When diffing commits, build each commit in a temporary `git worktree`
+    /// instead of checking it out in place. This leaves your checkout
+    /// untouched, works with local changes, and builds both commits in
+    /// parallel.
+    #[arg(long)]
+    worktree: bool,
+
+    /// Initialize git submodules in the temporary worktrees of `--worktree`.
+    #[arg(long, requires = "worktree")]
+    init_submodules: bool,
+
+    /// Get published versions from the given alternative registry, configured
+    /// in `.cargo/config.toml`, instead of from crates.io.
+    #[arg(long, value_name = "NAME")]
+    registry: Option<String>,
+
+    /// Get published versions from the registry with the given index URL,
+    /// e.g. `sparse+https://my-registry.example/index/` or
+    /// `file:///path/to/index`, instead of from crates.io.
+    #[arg(long, value_name = "URL", conflicts_with = "registry")]
+    index: Option<String>,
+
+    /// Get published versions without network access, from the cargo cache
+    /// or from a local registry or vendored crates configured with source
+    /// replacement in `.cargo/config.toml`.
+    #[arg(long)]
+    offline: bool,
+
+    /// Instead of printing the diff, print the smallest semver compatible
+    /// version that the working tree can be published as, given how its
+    /// public API differs from the published version. For example `0.4.2 ->
+    /// 0.5.0` if there are breaking changes.
+    #[arg(long)]
+    required_bump: bool,
+
+    /// Exit with failure if the version in `Cargo.toml` is lower than the
+    /// version printed by `--required-bump`.
+    #[arg(long, requires = "required_bump")]
+    deny_insufficient_version: bool,
+
+    /// Diff with default features and with every combination of the other
+    /// features, and report which feature sets each change affects.
+    #[arg(long, conflicts_with_all = ["feature_set", "required_bump"])]
+    feature_powerset: bool,
+
+    /// Diff with the given comma separated list of features instead of the
+    /// default features. Can be given several times to diff with several
+    /// feature sets, and report which feature sets each change affects. Use
+    /// `default` for the default features, and an empty value for no
+    /// features.
+    #[arg(long, value_name = "FEATURES", conflicts_with = "required_bump")]
+    feature_set: Vec<String>,
+
     #[clap(verbatim_doc_comment)]
     /// What to diff.
     ///
@@ -177,7 +338,7 @@ struct DiffArgs {
     args: Vec<String>,
 }
 
-#[derive(clap::Subcommand, Debug)]
+#[derive(clap::Subcommand, Debug, Clone)]
 enum Subcommand {
     /// Diff the public API against a published version of the crate, or between commits.
     ///
@@ -190,7 +351,14 @@ enum Subcommand {
     ///   diffed. See below for how that works.
     ///
     /// * args end with `.json` like in `file1.json file2.json` then rustdoc JSON file diffing will be
-    ///   performed.
+    ///   performed. A `.json` file can also be a public API serialized with the `serde` feature of
+    ///   the `public-api` library.
+    ///
+    /// * args end with `.txt` like in `public-api.txt` then the file is treated as a text snapshot
+    ///   of the public API, like the ones written by `public-api`'s `assert_eq_or_update()`. Since
+    ///   such a snapshot only contains text, the other public API is reduced to text before diffing.
+    ///
+    /// A single file arg is diffed against the current working tree.
     ///
     ///
     /// EXAMPLES:
@@ -220,6 +388,18 @@ enum Subcommand {
     ///
     ///     cargo public-api diff first.json second.json
     ///
+    /// Diff a checked in public API snapshot against the current working tree:
+    ///
+    ///     cargo public-api diff tests/public-api.txt
+    ///
+    /// Diff another copy of the crate, or a `.crate` archive, against the current working tree:
+    ///
+    ///     cargo public-api diff ../mycrate-old ./
+    ///
+    /// Print the smallest version the current working tree can be published as:
+    ///
+    ///     cargo public-api diff --required-bump
+    ///
     ///
     /// HOW COMMIT DIFFING WORKS:
     /// =========================
@@ -240,9 +420,85 @@ enum Subcommand {
     /// Using the current git repo has the benefit of making it likely for the build to succeed. If we e.g.
     /// were to git clone a temporary copy of a commit ourselves, the risk is high that additional steps are
     /// needed before a build can succeed. Such as the need to set up git submodules.
+    ///
+    /// With `--worktree`, each commit is instead checked out in a temporary `git worktree`, which is
+    /// removed afterwards. Your checkout is left untouched, so local changes are no problem. Use
+    /// `--init-submodules` if the build needs git submodules.
     #[clap(verbatim_doc_comment)]
     Diff(DiffArgs),
 
+    /// Summarize the public API, or compare the summaries of two public APIs.
+    ///
+    /// Counts items per kind and per module, `impl`s per kind, items with
+    /// many generic parameters, `unsafe` functions and `#[non_exhaustive]`
+    /// types. Re-export aliases are only counted in the summary, so that
+    /// re-exported items are not counted twice.
+    ///
+    /// Args are like for `diff`. Without args, the current working tree is
+    /// summarized. With one arg that is not a commit range, that public API is
+    /// summarized. With a commit range or two args, both public APIs are
+    /// summarized, with the difference between their counts.
+    ///
+    ///
+    /// EXAMPLES:
+    /// =========
+    ///
+    /// Summarize the public API of the current working tree:
+    ///
+    ///     cargo public-api stats
+    ///
+    /// Compare the summaries of two published versions:
+    ///
+    ///     cargo public-api -p example_api stats 0.1.0 0.2.0
+    ///
+    /// Compare the summaries of two commits:
+    ///
+    ///     cargo public-api stats v0.2.0..v0.3.0
+    #[clap(verbatim_doc_comment)]
+    Stats(StatsArgs),
+
+    /// List the items of the public API that match all of the given predicates.
+    ///
+    /// Each predicate is `key:value`:
+    ///
+    /// * `kind:fn` matches items of that kind, e.g. `struct`, `method` or `trait_impl`. `fn` matches
+    ///   functions and methods, and `impl` all kinds of `impl`s. Use `kind:struct,enum` for either.
+    ///
+    /// * `path:crate::io::*` matches items whose path matches the glob pattern, like with
+    ///   `--only-path`. A leading `crate` matches the name of the crate.
+    ///
+    /// * `name:read_*` matches items whose name matches the glob pattern.
+    ///
+    /// * `returns:Result` matches functions whose return type contains the given type. Paths can be
+    ///   left out. `async fn`s return `impl Future`.
+    ///
+    /// * `impl:Serialize` matches `impl`s of the given trait.
+    ///
+    /// * `unsafe:true` matches `unsafe` functions, traits and `impl`s. `unsafe:false` matches others.
+    ///
+    /// * `self:mut` matches methods that take `&mut self`. Other values are `ref` (`&self`), `value`
+    ///   (`self`), `other` (e.g. `self: Box<Self>`), `any` and `none`.
+    ///
+    /// * `text:Copy` matches items whose text contains the given text.
+    ///
+    ///
+    /// EXAMPLES:
+    /// =========
+    ///
+    /// List public functions that take `&mut self` and return `impl Future`:
+    ///
+    ///     cargo public-api query kind:fn self:mut 'returns:impl Future'
+    ///
+    /// List `impl`s of `Serialize`:
+    ///
+    ///     cargo public-api query impl:Serialize
+    ///
+    /// List `unsafe` items in the `io` module and its submodules:
+    ///
+    ///     cargo public-api query 'path:crate::io::**' unsafe:true
+    #[clap(verbatim_doc_comment)]
+    Query(QueryArgs),
+
     /// Generate completion scripts for many different shells.
     ///
     /// Example on how to generate and install the completion script for zsh:
@@ -275,6 +531,16 @@ enum MainTask {
         old_api: Box<dyn ApiSource>,
         new_api: Box<dyn ApiSource>,
     },
+    /// Print the version the working tree can be published as. `None` means
+    /// the latest published version is diffed against.
+    PrintRequiredBump {
+        published_version: Option<String>,
+    },
+    /// Summarize the public API of a crate, compared with `old_api` if given.
+    PrintStats {
+        old_api: Option<Box<dyn ApiSource>>,
+        new_api: Box<dyn ApiSource>,
+    },
     GenerateShellCompletionScript(clap_complete_command::Shell),
 }
 
@@ -285,12 +551,35 @@ pub enum Action {
     CheckDiff {
         diff: PublicApiDiff,
         deny: Vec<DenyMethod>,
+        allowlist: Allowlist,
     },
 
     /// Doing a `--diff-git-checkouts` involves doing `git checkout`s.
     /// Afterwards, we want to restore the original branch the user was on, to
     /// not mess up their work tree.
     RestoreBranch { name: String },
+
+    /// The `--deny-insufficient-version` arg allows the user to disallow a
+    /// version in `Cargo.toml` that is lower than the required version.
+    CheckVersion {
+        required: semver::Version,
+        actual: semver::Version,
+    },
+}
+
+/// What the main task resulted in. Used to summarize `--workspace` runs.
+pub enum Outcome {
+    Listed {
+        items: usize,
+    },
+    Diffed {
+        removed: usize,
+        changed: usize,
+        deprecated: usize,
+        moved: usize,
+        added: usize,
+    },
+    Other,
 }
 
 /// The string used by users to request a diff of the latest (in semver terms)
@@ -307,6 +596,26 @@ fn main_() -> Result<()> {
 
     let argst = get_args();
 
+    if argst.args.workspace
+        && !matches!(argst.args.subcommand, Some(Subcommand::Completions { .. }))
+    {
+        workspace::run_for_workspace(&argst)
+    } else {
+        let mut stale_entries = StaleEntries::default();
+        let result = run(&argst, &mut stdout(), &mut stale_entries);
+        stale_entries.warn();
+        result.map(|_| ())
+    }
+}
+
+/// Performs the main task and final actions requested by `argst`, writing
+/// output to `w`. Allowlist entries that do not match any change are collected
+/// in `stale_entries`.
+fn run(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    stale_entries: &mut StaleEntries,
+) -> Result<Outcome> {
     // A list of actions to perform after we have listed or diffed. Typical
     // examples: restore a git branch or check that a diff is allowed
     let mut final_actions = vec![];
@@ -324,34 +633,63 @@ fn main_() -> Result<()> {
 
     // Now we perform the main task
     let result = match main_task {
-        MainTask::PrintList { api } => print_public_items(&argst, api.as_ref()),
+        MainTask::PrintList { api } => print_public_items(argst, w, api.as_ref()),
         MainTask::PrintDiff { old_api, new_api } => print_diff(
-            &argst,
+            argst,
+            w,
             old_api.as_ref(),
             new_api.as_ref(),
             &mut final_actions,
         ),
+        MainTask::PrintRequiredBump { published_version } => {
+            print_required_bump(argst, w, published_version.as_deref(), &mut final_actions)
+        }
+        MainTask::PrintStats { old_api, new_api } => {
+            print_stats(argst, w, old_api.as_deref(), new_api.as_ref())
+        }
         MainTask::GenerateShellCompletionScript(shell) => {
-            shell.generate(
-                &mut Args::command().bin_name("cargo-public-api"),
-                &mut stdout(),
-            );
-            Ok(())
+            shell.generate(&mut Args::command().bin_name("cargo-public-api"), w);
+            Ok(Outcome::Other)
         }
     };
 
     // Handle any final actions, such as checking the diff and restoring the
     // original git branch
     for action in final_actions {
-        action.perform(&argst.args)?;
+        action.perform(&argst.args, stale_entries)?;
     }
 
     result
 }
 
 fn main_task(args: &Args) -> Result<MainTask> {
+    if args.feature_matrix && (args.subcommand.is_some() || args.rustdoc_json.is_some()) {
+        bail!("`--feature-matrix` can only be used to list the public API of a package");
+    }
+    if let Some(diff_args) = args.diff_args()
+        && (diff_args.feature_powerset || !diff_args.feature_set.is_empty())
+        && (!args.features.is_empty() || args.all_features || args.no_default_features)
+    {
+        bail!(
+            "`--feature-powerset` and `--feature-set` can not be combined with `--features`, `--all-features` or `--no-default-features`"
+        );
+    }
+    if !args.targets.is_empty()
+        && (args.rustdoc_json.is_some()
+            || args.diff_args().is_some_and(|a| {
+                a.feature_powerset || !a.feature_set.is_empty() || a.required_bump
+            }))
+    {
+        bail!(
+            "`--targets` can not be combined with `--rustdoc-json`, `--feature-powerset`, `--feature-set` or `--required-bump`"
+        );
+    }
+
     match &args.subcommand {
         Some(Subcommand::Diff(diff_args)) => main_task_from_diff_args(args, diff_args),
+        Some(Subcommand::Stats(stats_args)) => main_task_from_stats_args(args, stats_args),
+        // Fail early on invalid predicates, before building rustdoc JSON
+        Some(Subcommand::Query(_)) => args.query().map(|_| main_task_from_args(args)),
         Some(Subcommand::Completions { shell }) => {
             Ok(MainTask::GenerateShellCompletionScript(*shell))
         }
@@ -370,6 +708,8 @@ fn main_task_from_args(args: &Args) -> MainTask {
 fn arg_to_api_source(arg: Option<&str>) -> Result<Box<dyn ApiSource>> {
     match arg {
         Some(arg) if is_json_file(arg) => Ok(RustdocJson::new(arg.into()).boxed()),
+        Some(arg) if is_text_snapshot_file(arg) => Ok(TextSnapshot::new(arg.into()).boxed()),
+        Some(arg) if LocalCrate::is_local_crate(arg) => Ok(LocalCrate::new(arg.into()).boxed()),
         Some(arg) if semver::Version::parse(arg).is_ok() => {
             Ok(PublishedCrate::new(Some(arg)).boxed())
         }
@@ -379,6 +719,10 @@ fn arg_to_api_source(arg: Option<&str>) -> Result<Box<dyn ApiSource>> {
 }
 
 fn main_task_from_diff_args(args: &Args, diff_args: &DiffArgs) -> Result<MainTask> {
+    if diff_args.required_bump {
+        return main_task_for_required_bump(diff_args);
+    }
+
     if diff_args.args.len() > 2 {
         bail!(
             "Expected 1 or 2 arguments, but got {}",
@@ -390,6 +734,13 @@ fn main_task_from_diff_args(args: &Args, diff_args: &DiffArgs) -> Result<MainTas
     let second_arg = diff_args.args.get(1);
 
     let main_task = match (first_arg, second_arg) {
+        (Some(first), None) if is_json_file(first) || is_text_snapshot_file(first) => {
+            MainTask::print_diff(arg_to_api_source(Some(first))?, CurrentDir.boxed())
+        }
+        // Before `..` syntax, since paths like `../old` contain `..` too
+        (Some(first), None) if LocalCrate::is_local_crate(first) => {
+            MainTask::print_diff(LocalCrate::new(first.into()).boxed(), CurrentDir.boxed())
+        }
         (Some(first), None) if first.contains("...") => {
             bail!("Invalid git diff syntax: {first}. Use: rev1..rev2");
         }
@@ -421,18 +772,96 @@ fn main_task_from_diff_args(args: &Args, diff_args: &DiffArgs) -> Result<MainTas
     Ok(main_task)
 }
 
-/// We were requested to deny diffs, so make sure there is no diff
-fn check_diff(deny: &[DenyMethod], diff: &PublicApiDiff) -> Result<()> {
+fn main_task_from_stats_args(args: &Args, stats_args: &StatsArgs) -> Result<MainTask> {
+    let api_source = |arg: &str| {
+        if arg == LATEST_VERSION_ARG {
+            Ok(PublishedCrate::new(Some(arg)).boxed())
+        } else {
+            arg_to_api_source(Some(arg))
+        }
+    };
+
+    let (old_api, new_api) = match stats_args.args.as_slice() {
+        [] => (None, CurrentDir.boxed()),
+        // Paths like `../old` contain `..` too
+        [range] if range.contains("..") && !LocalCrate::is_local_crate(range) => {
+            let commits: Vec<_> = range.split("..").collect();
+            if range.contains("...") || commits.len() != 2 {
+                bail!("Invalid git diff syntax: {range}. Use: rev1..rev2");
+            }
+            (
+                Some(Commit::new(args, commits[0])?.boxed()),
+                Commit::new(args, commits[1])?.boxed(),
+            )
+        }
+        [arg] => (None, api_source(arg)?),
+        [old, new] => (Some(api_source(old)?), api_source(new)?),
+        args => bail!("Expected at most 2 arguments, but got {}", args.len()),
+    };
+
+    Ok(MainTask::PrintStats { old_api, new_api })
+}
+
+/// The required bump is always relative to a published version, so only
+/// published version args are allowed.
+fn main_task_for_required_bump(diff_args: &DiffArgs) -> Result<MainTask> {
+    match diff_args.args.as_slice() {
+        [] => Ok(MainTask::PrintRequiredBump {
+            published_version: None,
+        }),
+        [version] if semver::Version::parse(version).is_ok() || version == LATEST_VERSION_ARG => {
+            Ok(MainTask::PrintRequiredBump {
+                published_version: Some(version.clone()),
+            })
+        }
+        _ => bail!(
+            "`--required-bump` can only diff the working tree against a published version, e.g. `diff --required-bump 1.2.3`"
+        ),
+    }
+}
+
+/// We were requested to deny diffs, so make sure there is no diff, except for
+/// changes accepted by the allowlist
+fn check_diff(
+    deny: &[DenyMethod],
+    diff: &PublicApiDiff,
+    allowlist: &Allowlist,
+    stale_entries: &mut StaleEntries,
+) -> Result<()> {
+    stale_entries.retain(allowlist.stale_entries(diff));
+
     let mut violations = crate::error::Violations::new();
     for d in deny {
-        if d.deny_added() && !diff.added.is_empty() {
-            violations.extend_added(diff.added.iter().cloned());
+        // Moved items used to be reported as removed and added, so keep
+        // denying them with `--deny removed` and `--deny added`
+        if d.deny_added() {
+            violations.extend_added(
+                diff.added
+                    .iter()
+                    .chain(diff.moved.iter().map(|m| &m.new))
+                    .filter(|item| !allowlist.accepts(item))
+                    .cloned(),
+            );
         }
-        if d.deny_changed() && !diff.changed.is_empty() {
-            violations.extend_changed(diff.changed.iter().cloned());
+        // Newly deprecated items used to be reported as changed, so keep
+        // denying them with `--deny changed`
+        if d.deny_changed() {
+            violations.extend_changed(
+                diff.changed
+                    .iter()
+                    .chain(&diff.deprecated)
+                    .filter(|c| !allowlist.accepts(&c.old) && !allowlist.accepts(&c.new))
+                    .cloned(),
+            );
         }
-        if d.deny_removed() && !diff.removed.is_empty() {
-            violations.extend_removed(diff.removed.iter().cloned());
+        if d.deny_removed() {
+            violations.extend_removed(
+                diff.removed
+                    .iter()
+                    .chain(diff.moved.iter().map(|m| &m.old))
+                    .filter(|item| !allowlist.accepts(item))
+                    .cloned(),
+            );
         }
     }
 
@@ -443,40 +872,297 @@ fn check_diff(deny: &[DenyMethod], diff: &PublicApiDiff) -> Result<()> {
     }
 }
 
-fn print_public_items(argst: &ArgsAndToolchain, public_api: &dyn ApiSource) -> Result<()> {
-    Plain::print_items(
-        &mut stdout(),
-        &argst.args,
-        public_api.obtain_api(argst)?.items(),
-    )?;
+fn print_public_items(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    public_api: &dyn ApiSource,
+) -> Result<Outcome> {
+    if argst.args.feature_matrix {
+        return print_feature_matrix(argst, w, public_api);
+    }
+    if !argst.args.targets.is_empty() {
+        return print_target_matrix(argst, w, public_api);
+    }
 
-    Ok(())
+    let public_api = public_api.obtain_api(argst)?;
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_items(w, &argst.args, public_api.items())?,
+        OutputFormat::Json => Json::print_items(w, public_api.items())?,
+        OutputFormat::Markdown => Markdown::print_items(w, public_api.items())?,
+    }
+
+    Ok(Outcome::Listed {
+        items: public_api.items().count(),
+    })
+}
+
+fn print_feature_matrix(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    public_api: &dyn ApiSource,
+) -> Result<Outcome> {
+    let feature_sets = feature_sets::feature_sets(&argst.args)?;
+    let items = feature_sets::public_api_with_required_features(argst, public_api, &feature_sets)?;
+    let items_with_cfg = || {
+        items
+            .iter()
+            .map(|(item, requirement)| (item, requirement.cfg()))
+    };
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_items_with_cfg(w, &argst.args, items_with_cfg())?,
+        OutputFormat::Json => Json::print_items_with_cfg(w, items_with_cfg())?,
+        OutputFormat::Markdown => Markdown::print_items_with_cfg(w, items_with_cfg())?,
+    }
+
+    Ok(Outcome::Listed { items: items.len() })
+}
+
+fn print_target_matrix(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    public_api: &dyn ApiSource,
+) -> Result<Outcome> {
+    let targets = targets::targets(argst);
+    let items = targets::public_api_with_targets(argst, public_api, &targets)?;
+    let items_with_cfg = || items.iter().map(|(item, cfg)| (item, cfg.clone()));
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_items_with_cfg(w, &argst.args, items_with_cfg())?,
+        OutputFormat::Json => Json::print_items_with_cfg(w, items_with_cfg())?,
+        OutputFormat::Markdown => Markdown::print_items_with_cfg(w, items_with_cfg())?,
+    }
+
+    Ok(Outcome::Listed { items: items.len() })
 }
 
 fn print_diff(
     argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
     old: &dyn ApiSource,
     new: &dyn ApiSource,
     final_actions: &mut Vec<Action>,
-) -> Result<()> {
-    fn check_diff(deny: &[DenyMethod], diff: PublicApiDiff) -> Action {
+) -> Result<Outcome> {
+    fn check_diff(deny: &[DenyMethod], diff: PublicApiDiff, allowlist: Allowlist) -> Action {
         Action::CheckDiff {
             diff,
             deny: deny.to_owned(),
+            allowlist,
         }
     }
 
-    let old = old.obtain_api(argst)?;
-    let new = new.obtain_api(argst)?;
-    let diff = PublicApiDiff::between(old, new);
+    // Read the allowlist before the time-consuming work so that mistakes in
+    // it are found early
+    let allowlist = match argst.args.diff_args().and_then(|a| a.allowlist.as_deref()) {
+        Some(path) => Allowlist::from_file(path)?,
+        None => Allowlist::default(),
+    };
 
-    Plain::print_diff(&mut stdout(), &argst.args, &diff)?;
+    let diff_args = argst.args.diff_args();
+    if diff_args.is_some_and(|a| a.feature_powerset || !a.feature_set.is_empty()) {
+        return print_diff_per_feature_set(argst, w, old, new, final_actions, allowlist);
+    }
+    if !argst.args.targets.is_empty() {
+        return print_diff_per_target(argst, w, old, new, final_actions, allowlist);
+    }
+
+    let diff = diff_apis(argst, old, new)?;
+
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_diff(w, &argst.args, &diff)?,
+        OutputFormat::Json => Json::print_diff(w, &diff)?,
+        OutputFormat::Markdown => Markdown::print_diff(w, &diff)?,
+    }
+
+    let outcome = Outcome::Diffed {
+        removed: diff.removed.len(),
+        changed: diff.changed.len(),
+        deprecated: diff.deprecated.len(),
+        moved: diff.moved.len(),
+        added: diff.added.len(),
+    };
 
     if let Some(Some(deny)) = argst.args.diff_args().map(|a| &a.deny) {
-        final_actions.push(check_diff(deny, diff));
+        final_actions.push(check_diff(deny, diff, allowlist));
+    }
+
+    Ok(outcome)
+}
+
+/// Diffs the public API of `old` and `new`. If either of them is a text
+/// snapshot, both are diffed as text.
+fn diff_apis(
+    argst: &ArgsAndToolchain,
+    old: &dyn ApiSource,
+    new: &dyn ApiSource,
+) -> Result<PublicApiDiff> {
+    let (mut old_api, mut new_api) = if old.builds_in_isolation() && new.builds_in_isolation() {
+        std::thread::scope(|s| {
+            let old_api = s.spawn(|| old.obtain_api(argst));
+            let new_api = new.obtain_api(argst);
+            let old_api = old_api
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            Ok::<_, anyhow::Error>((old_api?, new_api?))
+        })?
+    } else {
+        (old.obtain_api(argst)?, new.obtain_api(argst)?)
+    };
+    if old.text_only() || new.text_only() {
+        old_api = PublicApi::from_text_snapshot(&old_api.to_string());
+        new_api = PublicApi::from_text_snapshot(&new_api.to_string());
     }
+    Ok(PublicApiDiff::between(old_api, new_api))
+}
 
-    Ok(())
+fn print_diff_per_feature_set(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    old: &dyn ApiSource,
+    new: &dyn ApiSource,
+    final_actions: &mut Vec<Action>,
+    allowlist: Allowlist,
+) -> Result<Outcome> {
+    let diff_args = argst.args.diff_args().expect("only called when diffing");
+    let feature_sets = if diff_args.feature_powerset {
+        feature_sets::feature_powerset(&argst.args)?
+    } else {
+        (diff_args.feature_set.iter())
+            .map(|value| feature_sets::parse_feature_set(value))
+            .collect()
+    };
+    let merged = feature_sets::diff_with_feature_sets(argst, old, new, &feature_sets)?;
+
+    let describe = |present_in: &[usize]| feature_sets::describe(&feature_sets, present_in);
+    let configurations = |present_in: &[usize]| {
+        json::Configurations::FeatureSets(
+            (present_in.iter())
+                .map(|&i| feature_sets[i].to_string())
+                .collect(),
+        )
+    };
+    print_merged_diff(
+        argst,
+        w,
+        merged,
+        describe,
+        configurations,
+        final_actions,
+        allowlist,
+    )
+}
+
+fn print_diff_per_target(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    old: &dyn ApiSource,
+    new: &dyn ApiSource,
+    final_actions: &mut Vec<Action>,
+    allowlist: Allowlist,
+) -> Result<Outcome> {
+    let targets = targets::targets(argst);
+    let merged = targets::diff_with_targets(argst, old, new, &targets)?;
+
+    let describe = |present_in: &[usize]| targets::describe(&targets, present_in);
+    let configurations = |present_in: &[usize]| {
+        json::Configurations::Targets(present_in.iter().map(|&i| targets[i].clone()).collect())
+    };
+    print_merged_diff(
+        argst,
+        w,
+        merged,
+        describe,
+        configurations,
+        final_actions,
+        allowlist,
+    )
+}
+
+/// Prints the diffs of several build configurations, grouped by the
+/// configurations each change affects. All changes together are what `--deny`
+/// checks.
+fn print_merged_diff(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    merged: MergedDiff,
+    describe: impl Fn(&[usize]) -> String,
+    configurations: impl Fn(&[usize]) -> json::Configurations,
+    final_actions: &mut Vec<Action>,
+    allowlist: Allowlist,
+) -> Result<Outcome> {
+    let groups = || (merged.groups.iter()).map(|group| (describe(&group.present_in), &group.diff));
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_diffs(w, &argst.args, groups())?,
+        OutputFormat::Json => Json::print_diffs(
+            w,
+            (merged.groups.iter()).map(|group| (configurations(&group.present_in), &group.diff)),
+        )?,
+        OutputFormat::Markdown => Markdown::print_diffs(w, groups())?,
+    }
+
+    let diff = merged.union;
+    let outcome = Outcome::Diffed {
+        removed: diff.removed.len(),
+        changed: diff.changed.len(),
+        deprecated: diff.deprecated.len(),
+        moved: diff.moved.len(),
+        added: diff.added.len(),
+    };
+
+    if let Some(Some(deny)) = argst.args.diff_args().map(|a| &a.deny) {
+        final_actions.push(Action::CheckDiff {
+            diff,
+            deny: deny.clone(),
+            allowlist,
+        });
+    }
+
+    Ok(outcome)
+}
+
+fn print_stats(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    old: Option<&dyn ApiSource>,
+    new: &dyn ApiSource,
+) -> Result<Outcome> {
+    let old_api = old.map(|old| old.obtain_api(argst)).transpose()?;
+    let new_api = new.obtain_api(argst)?;
+    let sections = stats::stats(old_api.as_ref(), &new_api);
+
+    match argst.args.output_format {
+        OutputFormat::Plain => Plain::print_stats(w, &sections)?,
+        OutputFormat::Json => Json::print_stats(w, &sections)?,
+        OutputFormat::Markdown => Markdown::print_stats(w, &sections)?,
+    }
+
+    Ok(Outcome::Other)
+}
+
+fn print_required_bump(
+    argst: &ArgsAndToolchain,
+    w: &mut dyn Write,
+    published_version: Option<&str>,
+    final_actions: &mut Vec<Action>,
+) -> Result<Outcome> {
+    let published = published_crate::resolve_version(published_version, &argst.args)?;
+    let old = PublishedCrate::new(Some(&published.to_string())).obtain_api(argst)?;
+    let new = CurrentDir.obtain_api(argst)?;
+    let diff = PublicApiDiff::between(old, new);
+
+    let required = version_bump::next_version(&published, diff.classify().required_bump());
+    writeln!(w, "{published} -> {required}")?;
+
+    if argst
+        .args
+        .diff_args()
+        .is_some_and(|a| a.deny_insufficient_version)
+    {
+        final_actions.push(Action::CheckVersion {
+            required,
+            actual: version_bump::manifest_version(&argst.args)?,
+        });
+    }
+
+    Ok(Outcome::Other)
 }
 
 impl MainTask {
@@ -494,20 +1180,37 @@ impl MainTask {
                 old_api.changes_commit() || new_api.changes_commit()
             }
             MainTask::PrintList { api } => api.changes_commit(),
-            MainTask::GenerateShellCompletionScript(_) => false,
+            MainTask::PrintStats { old_api, new_api } => {
+                old_api.as_ref().is_some_and(|api| api.changes_commit()) || new_api.changes_commit()
+            }
+            MainTask::PrintRequiredBump { .. } | MainTask::GenerateShellCompletionScript(_) => {
+                false
+            }
         }
     }
 }
 
 impl Action {
-    fn perform(&self, args: &Args) -> Result<()> {
+    fn perform(&self, args: &Args, stale_entries: &mut StaleEntries) -> Result<()> {
         match self {
-            Action::CheckDiff { deny, diff } => {
-                check_diff(deny, diff)?;
+            Action::CheckDiff {
+                deny,
+                diff,
+                allowlist,
+            } => {
+                check_diff(deny, diff, allowlist, stale_entries)?;
             }
             Action::RestoreBranch { name } => {
                 git_checkout(args, name)?;
             }
+            Action::CheckVersion { required, actual } => {
+                if actual < required {
+                    bail!(error::Error::InsufficientVersion {
+                        required: required.clone(),
+                        actual: actual.clone(),
+                    });
+                }
+            }
         };
         Ok(())
     }
@@ -526,6 +1229,14 @@ impl Args {
         self.omits(Omit::AutoDerivedImpls)
     }
 
+    fn omit_doc_hidden(&self) -> bool {
+        self.omits(Omit::DocHidden)
+    }
+
+    fn omit_unstable(&self) -> bool {
+        self.omits(Omit::Unstable)
+    }
+
     fn omits(&self, to_omit: Omit) -> bool {
         self.omit.iter().flatten().any(|o| *o == to_omit)
     }
@@ -534,10 +1245,24 @@ impl Args {
         self.includes(Include::FunctionParameterNames)
     }
 
+    fn include_doc_hidden(&self) -> bool {
+        self.includes(Include::DocHidden)
+    }
+
     fn includes(&self, to_include: Include) -> bool {
         self.include.iter().flatten().any(|i| *i == to_include)
     }
 
+    /// The predicates of the `query` subcommand, if used.
+    fn query(&self) -> Result<Option<public_api::query::Query>> {
+        match &self.subcommand {
+            Some(Subcommand::Query(query_args)) => {
+                Ok(Some(public_api::query::Query::new(&query_args.predicates)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn git_root(&self) -> Result<PathBuf> {
         git_utils::git_root_from_manifest_path(self.manifest_path.as_path())
     }
@@ -638,6 +1363,10 @@ fn is_json_file(file_name: impl AsRef<str>) -> bool {
     Path::extension(Path::new(file_name.as_ref())).is_some_and(|a| a.eq_ignore_ascii_case("json"))
 }
 
+fn is_text_snapshot_file(file_name: impl AsRef<str>) -> bool {
+    Path::extension(Path::new(file_name.as_ref())).is_some_and(|a| a.eq_ignore_ascii_case("txt"))
+}
+
 /// Helper to reduce code duplication. We can't add [`Args`] to
 /// [`git_utils::git_checkout()`] itself, because it is used in contexts where
 /// [`Args`] is not available (namely in tests).
@@ -0,0 +1,176 @@
+//! GitHub flavored Markdown output for `--output-format markdown`, e.g. for
+//! posting the public API diff as a comment on a pull request.
+
+use std::io::{Result, Write};
+
+use public_api::{
+    PublicItem,
+    diff::{ChangedPublicItem, PublicApiDiff},
+};
+
+use crate::plain::reexport_note;
+use crate::stats::Section;
+
+pub struct Markdown;
+
+impl Markdown {
+    pub fn print_items<'a>(
+        w: &mut dyn Write,
+        items: impl Iterator<Item = &'a PublicItem>,
+    ) -> Result<()> {
+        writeln!(w, "```rust")?;
+        for item in items {
+            writeln!(w, "{item}{}", reexport_note(item))?;
+        }
+        writeln!(w, "```")
+    }
+
+    pub fn print_items_with_cfg<'a>(
+        w: &mut dyn Write,
+        items: impl Iterator<Item = (&'a PublicItem, Option<String>)>,
+    ) -> Result<()> {
+        writeln!(w, "```rust")?;
+        for (item, cfg) in items {
+            match cfg {
+                Some(cfg) => writeln!(w, "#[cfg({cfg})] {item}{}", reexport_note(item))?,
+                None => writeln!(w, "{item}{}", reexport_note(item))?,
+            }
+        }
+        writeln!(w, "```")
+    }
+
+    pub fn print_diff(w: &mut dyn Write, diff: &PublicApiDiff) -> Result<()> {
+        let (removed, changed, added) = (diff.removed.len(), diff.changed.len(), diff.added.len());
+        write!(
+            w,
+            "**Public API diff:** {removed} removed, {changed} changed, {added} added"
+        )?;
+        if !diff.deprecated.is_empty() {
+            write!(w, ", {} deprecated", diff.deprecated.len())?;
+        }
+        if !diff.moved.is_empty() {
+            write!(w, ", {} moved", diff.moved.len())?;
+        }
+        writeln!(w)?;
+        writeln!(w)?;
+
+        if diff.is_empty() {
+            return writeln!(w, "No changes to the public API.");
+        }
+
+        print_section(
+            w,
+            "Removed items from the public API",
+            &diff.removed,
+            |w, item| match diff.still_available_at(item) {
+                Some(path) => {
+                    writeln!(w, "-{item} // re-export removed, still available at {path}")
+                }
+                None => writeln!(w, "-{item}{}", reexport_note(item)),
+            },
+        )?;
+        let print_changed = |w: &mut dyn Write, changed: &ChangedPublicItem| {
+            writeln!(w, "-{}{}", changed.old, reexport_note(&changed.old))?;
+            writeln!(w, "+{}{}", changed.new, reexport_note(&changed.new))
+        };
+        print_section(
+            w,
+            "Changed items in the public API",
+            &diff.changed,
+            print_changed,
+        )?;
+        print_section(
+            w,
+            "Deprecated items in the public API",
+            &diff.deprecated,
+            print_changed,
+        )?;
+        print_section(
+            w,
+            "Moved or renamed items in the public API",
+            &diff.moved,
+            print_changed,
+        )?;
+        print_section(
+            w,
+            "Added items to the public API",
+            &diff.added,
+            |w, item| writeln!(w, "+{item}{}", reexport_note(item)),
+        )
+    }
+
+    /// Prints several diffs, each under a heading that describes the
+    /// configurations it is for.
+    pub fn print_diffs<'a>(
+        w: &mut dyn Write,
+        diffs: impl Iterator<Item = (String, &'a PublicApiDiff)>,
+    ) -> Result<()> {
+        for (configurations, diff) in diffs {
+            writeln!(w, "### Diff with {configurations}")?;
+            writeln!(w)?;
+            Self::print_diff(w, diff)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints each section of stats as a table.
+    pub fn print_stats(w: &mut dyn Write, sections: &[Section]) -> Result<()> {
+        let comparing = (sections.iter())
+            .flat_map(|section| &section.rows)
+            .any(|row| row.old.is_some());
+        for section in sections {
+            writeln!(w, "### {}", section.title)?;
+            writeln!(w)?;
+            if section.rows.is_empty() {
+                writeln!(w, "(none)")?;
+                writeln!(w)?;
+                continue;
+            }
+            if comparing {
+                writeln!(w, "| | Old | New | Delta |")?;
+                writeln!(w, "|---|---:|---:|---:|")?;
+            } else {
+                writeln!(w, "| | Count |")?;
+                writeln!(w, "|---|---:|")?;
+            }
+            for row in &section.rows {
+                match (row.old, row.delta()) {
+                    (Some(old), Some(delta)) => {
+                        writeln!(w, "| {} | {old} | {} | {delta:+} |", row.label, row.new)?
+                    }
+                    _ => writeln!(w, "| {} | {} |", row.label, row.new)?,
+                }
+            }
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints a collapsible section with the items in a fenced `diff` block, so
+/// that GitHub colors removed and added lines. Empty sections are skipped to
+/// keep comments short.
+fn print_section<T>(
+    w: &mut dyn Write,
+    header: &str,
+    items: &[T],
+    print_fn: impl Fn(&mut dyn Write, &T) -> Result<()>,
+) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w, "<details>")?;
+    writeln!(w, "<summary>{header} ({})</summary>", items.len())?;
+    writeln!(w)?;
+    writeln!(w, "```diff")?;
+    for item in items {
+        print_fn(w, item)?;
+    }
+    writeln!(w, "```")?;
+    writeln!(w)?;
+    writeln!(w, "</details>")?;
+    writeln!(w)
+}
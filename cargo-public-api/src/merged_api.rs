@@ -0,0 +1,49 @@
+//! Merges the public APIs of several build configurations, e.g. different
+//! feature sets, into one list of items where each item knows what
+//! configurations it is part of.
+
+use std::collections::HashMap;
+
+use public_api::{PublicApi, PublicItem};
+
+/// An item of a [`MergedApi`].
+pub struct MergedItem {
+    pub item: PublicItem,
+
+    /// Indices of the configurations that the item is part of, in the same
+    /// order as the public APIs given to [`MergedApi::new`].
+    pub present_in: Vec<usize>,
+}
+
+/// The union of the items of several public APIs.
+pub struct MergedApi {
+    pub items: Vec<MergedItem>,
+}
+
+impl MergedApi {
+    /// Merges the items of `apis`. Items are the same if they render the same,
+    /// since ids are different in each build of rustdoc JSON.
+    pub fn new(apis: Vec<PublicApi>) -> Self {
+        let mut items: Vec<MergedItem> = vec![];
+        let mut index_of_item: HashMap<String, usize> = HashMap::new();
+        for (configuration, api) in apis.into_iter().enumerate() {
+            for item in api.into_items() {
+                match index_of_item.get(&item.to_string()) {
+                    // An API can contain several items that render the same
+                    Some(&index) if items[index].present_in.last() == Some(&configuration) => {}
+                    Some(&index) => items[index].present_in.push(configuration),
+                    None => {
+                        index_of_item.insert(item.to_string(), items.len());
+                        items.push(MergedItem {
+                            item,
+                            present_in: vec![configuration],
+                        });
+                    }
+                }
+            }
+        }
+
+        items.sort_by(|a, b| a.item.grouping_cmp(&b.item));
+        Self { items }
+    }
+}
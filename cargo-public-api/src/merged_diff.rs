@@ -0,0 +1,137 @@
+//! Merges the public API diffs of several build configurations, e.g. different
+//! feature sets, so that each change is reported once together with the
+//! configurations it affects.
+
+use std::collections::HashMap;
+
+use public_api::PublicItem;
+use public_api::diff::{ChangedPublicItem, PublicApiDiff};
+
+/// The changes that affect the same configurations.
+pub struct DiffGroup {
+    /// Indices of the configurations affected by the changes, in the same
+    /// order as the diffs given to [`MergedDiff::new`].
+    pub present_in: Vec<usize>,
+
+    pub diff: PublicApiDiff,
+}
+
+/// The diffs of several configurations, with duplicate changes merged.
+pub struct MergedDiff {
+    /// Changes grouped by the configurations they affect. Changes that affect
+    /// all configurations come first.
+    pub groups: Vec<DiffGroup>,
+
+    /// All changes of all configurations, each change only once.
+    pub union: PublicApiDiff,
+}
+
+enum Change {
+    Removed(PublicItem),
+    Changed(ChangedPublicItem),
+    Deprecated(ChangedPublicItem),
+    Moved(ChangedPublicItem),
+    Added(PublicItem),
+}
+
+impl Change {
+    /// Changes are the same if they render the same, since ids are different
+    /// in each build of rustdoc JSON.
+    fn key(&self) -> String {
+        match self {
+            Self::Removed(item) => format!("-{item}"),
+            Self::Changed(changed) => format!("~{}\n{}", changed.old, changed.new),
+            Self::Deprecated(changed) => format!("!{}\n{}", changed.old, changed.new),
+            Self::Moved(moved) => format!(">{}\n{}", moved.old, moved.new),
+            Self::Added(item) => format!("+{item}"),
+        }
+    }
+
+    fn add_to(self, diff: &mut PublicApiDiff) {
+        match self {
+            Self::Removed(item) => diff.removed.push(item),
+            Self::Changed(changed) => diff.changed.push(changed),
+            Self::Deprecated(changed) => diff.deprecated.push(changed),
+            Self::Moved(moved) => diff.moved.push(moved),
+            Self::Added(item) => diff.added.push(item),
+        }
+    }
+}
+
+impl MergedDiff {
+    /// Merges `diffs`. If there are no changes at all, there is one group with
+    /// an empty diff that affects all configurations.
+    pub fn new(diffs: Vec<PublicApiDiff>) -> Self {
+        let configurations = diffs.len();
+
+        let mut changes: Vec<(Change, Vec<usize>)> = vec![];
+        let mut index_of_change: HashMap<String, usize> = HashMap::new();
+        for (configuration, diff) in diffs.into_iter().enumerate() {
+            let diff_changes = (diff.removed.into_iter().map(Change::Removed))
+                .chain(diff.changed.into_iter().map(Change::Changed))
+                .chain(diff.deprecated.into_iter().map(Change::Deprecated))
+                .chain(diff.moved.into_iter().map(Change::Moved))
+                .chain(diff.added.into_iter().map(Change::Added));
+            for change in diff_changes {
+                let key = change.key();
+                match index_of_change.get(&key) {
+                    // A diff can contain several changes that render the same
+                    Some(&index) if changes[index].1.last() == Some(&configuration) => {}
+                    Some(&index) => changes[index].1.push(configuration),
+                    None => {
+                        index_of_change.insert(key, changes.len());
+                        changes.push((change, vec![configuration]));
+                    }
+                }
+            }
+        }
+
+        let mut union = PublicApiDiff::default();
+        let mut groups: Vec<DiffGroup> = vec![];
+        for (change, present_in) in changes {
+            let group = match groups.iter().position(|g| g.present_in == present_in) {
+                Some(index) => &mut groups[index],
+                None => {
+                    groups.push(DiffGroup {
+                        present_in,
+                        diff: PublicApiDiff::default(),
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+            change.add_to(&mut group.diff);
+        }
+        for group in &mut groups {
+            sort(&mut group.diff);
+            union.removed.extend(group.diff.removed.iter().cloned());
+            union.changed.extend(group.diff.changed.iter().cloned());
+            union
+                .deprecated
+                .extend(group.diff.deprecated.iter().cloned());
+            union.moved.extend(group.diff.moved.iter().cloned());
+            union.added.extend(group.diff.added.iter().cloned());
+        }
+        sort(&mut union);
+
+        if groups.is_empty() {
+            groups.push(DiffGroup {
+                present_in: (0..configurations).collect(),
+                diff: PublicApiDiff::default(),
+            });
+        }
+        groups.sort_by(|a, b| {
+            (b.present_in.len().cmp(&a.present_in.len()))
+                .then_with(|| a.present_in.cmp(&b.present_in))
+        });
+
+        Self { groups, union }
+    }
+}
+
+fn sort(diff: &mut PublicApiDiff) {
+    diff.removed.sort_by(PublicItem::grouping_cmp);
+    diff.changed.sort_by(ChangedPublicItem::grouping_cmp);
+    diff.deprecated.sort_by(ChangedPublicItem::grouping_cmp);
+    diff.moved.sort_by(ChangedPublicItem::grouping_cmp);
+    diff.added.sort_by(PublicItem::grouping_cmp);
+}
@@ -1,9 +1,14 @@
 use std::io::{Result, Write};
 
 use nu_ansi_term::{AnsiString, AnsiStrings, Color, Style};
-use public_api::{PublicItem, diff::PublicApiDiff, tokens::Token};
+use public_api::{
+    PublicItem,
+    diff::{ChangedPublicItem, PublicApiDiff, TokenRun},
+    tokens::Token,
+};
 
 use crate::Args;
+use crate::stats::Section;
 
 pub struct Plain;
 
@@ -20,6 +25,23 @@ impl Plain {
         Ok(())
     }
 
+    /// Prints each item with the `cfg` condition it requires, if any, as an
+    /// annotation, e.g. `#[cfg(feature = "std")] pub fn ...`.
+    pub fn print_items_with_cfg<'a>(
+        w: &mut dyn Write,
+        args: &Args,
+        items: impl Iterator<Item = (&'a PublicItem, Option<String>)>,
+    ) -> Result<()> {
+        for (item, cfg) in items {
+            if let Some(cfg) = cfg {
+                write!(w, "#[cfg({cfg})] ")?;
+            }
+            print_item(args, w, item)?;
+        }
+
+        Ok(())
+    }
+
     pub fn print_diff(w: &mut dyn Write, args: &Args, diff: &PublicApiDiff) -> Result<()> {
         let use_color = color_active(args.color);
 
@@ -29,56 +51,157 @@ impl Plain {
             &diff.removed,
             |w, item| {
                 if use_color {
-                    writeln!(w, "-{}", color_item(item))
+                    write!(w, "-{}", color_item(item))?;
                 } else {
-                    writeln!(w, "-{item}")
+                    write!(w, "-{item}")?;
                 }
+                write_reexport_note(w, item, use_color)?;
+                if let Some(path) = diff.still_available_at(item) {
+                    let note = format!(" // re-export removed, still available at {path}");
+                    if use_color {
+                        write!(w, "{}", Style::new().dimmed().paint(note))?;
+                    } else {
+                        write!(w, "{note}")?;
+                    }
+                }
+                writeln!(w)
             },
         )?;
 
+        let print_changed_item = |w: &mut dyn Write, changed_item: &ChangedPublicItem| {
+            let (old, new) = if use_color {
+                let token_diff = changed_item.token_diff();
+                (
+                    color_item_with_diff(&token_diff, true),
+                    color_item_with_diff(&token_diff, false),
+                )
+            } else {
+                (changed_item.old.to_string(), changed_item.new.to_string())
+            };
+            write!(w, "-{old}")?;
+            write_reexport_note(w, &changed_item.old, use_color)?;
+            write!(w, "\n+{new}")?;
+            write_reexport_note(w, &changed_item.new, use_color)?;
+            writeln!(w)
+        };
+
         print_items_with_header(
             w,
             "Changed items in the public API",
             &diff.changed,
-            |w, changed_item| {
-                if use_color {
-                    let old_tokens: Vec<&Token> = changed_item.old.tokens().collect();
-                    let new_tokens: Vec<&Token> = changed_item.new.tokens().collect();
-                    let diff_slice = diff::slice(old_tokens.as_slice(), new_tokens.as_slice());
-                    writeln!(
-                        w,
-                        "-{}\n+{}",
-                        color_item_with_diff(&diff_slice, true),
-                        color_item_with_diff(&diff_slice, false),
-                    )
-                } else {
-                    writeln!(w, "-{}\n+{}", changed_item.old, changed_item.new)
-                }
-            },
+            print_changed_item,
         )?;
 
+        // Unlike the other sections, this one is only printed when there is
+        // something in it, so the output for crates that don't deprecate
+        // anything stays the same
+        if !diff.deprecated.is_empty() {
+            print_items_with_header(
+                w,
+                "Deprecated items in the public API",
+                &diff.deprecated,
+                print_changed_item,
+            )?;
+        }
+
+        if !diff.moved.is_empty() {
+            print_items_with_header(
+                w,
+                "Moved or renamed items in the public API",
+                &diff.moved,
+                print_changed_item,
+            )?;
+        }
+
         print_items_with_header(
             w,
             "Added items to the public API",
             &diff.added,
             |w, item| {
                 if use_color {
-                    writeln!(w, "+{}", color_item(item))
+                    write!(w, "+{}", color_item(item))?;
                 } else {
-                    writeln!(w, "+{item}")
+                    write!(w, "+{item}")?;
                 }
+                write_reexport_note(w, item, use_color)?;
+                writeln!(w)
             },
         )?;
 
         Ok(())
     }
+
+    /// Prints several diffs, each under a header that describes the
+    /// configurations it is for.
+    pub fn print_diffs<'a>(
+        w: &mut dyn Write,
+        args: &Args,
+        diffs: impl Iterator<Item = (String, &'a PublicApiDiff)>,
+    ) -> Result<()> {
+        for (configurations, diff) in diffs {
+            let header = format!("Diff with {configurations}");
+            writeln!(w, "{header}")?;
+            writeln!(w, "{}", "#".repeat(header.len()))?;
+            writeln!(w)?;
+            Self::print_diff(w, args, diff)?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints each section of stats as a column of counts, or as old and new
+    /// counts and their difference when two public APIs are compared.
+    pub fn print_stats(w: &mut dyn Write, sections: &[Section]) -> Result<()> {
+        for section in sections {
+            let width = (section.rows.iter())
+                .map(|row| row.label.len())
+                .max()
+                .unwrap_or_default();
+            print_items_with_header(w, section.title, &section.rows, |w, row| {
+                match (row.old, row.delta()) {
+                    (Some(old), Some(delta)) => writeln!(
+                        w,
+                        "{:<width$}  {old:>6} -> {:>6}  ({delta:+})",
+                        row.label, row.new
+                    ),
+                    _ => writeln!(w, "{:<width$}  {:>6}", row.label, row.new),
+                }
+            })?;
+        }
+
+        Ok(())
+    }
 }
 
 fn print_item(args: &Args, w: &mut dyn Write, item: &PublicItem) -> Result<()> {
-    if color_active(args.color) {
-        writeln!(w, "{}", color_item(item))
+    let use_color = color_active(args.color);
+    if use_color {
+        write!(w, "{}", color_item(item))?;
     } else {
-        writeln!(w, "{item}")
+        write!(w, "{item}")?;
+    }
+    write_reexport_note(w, item, use_color)?;
+    writeln!(w)
+}
+
+/// The paths an item is re-exported at with `--collapse-reexports`, e.g.
+/// ` // re-exported at my_crate::Foo`, or an empty string if there are none.
+/// The paths are not part of the tokens of the item, so they are rendered as a
+/// comment after it.
+pub fn reexport_note(item: &PublicItem) -> String {
+    if item.reexport_paths().is_empty() {
+        String::new()
+    } else {
+        format!(" // re-exported at {}", item.reexport_paths().join(", "))
+    }
+}
+
+fn write_reexport_note(w: &mut dyn Write, item: &PublicItem, use_color: bool) -> Result<()> {
+    let note = reexport_note(item);
+    if use_color && !note.is_empty() {
+        write!(w, "{}", Style::new().dimmed().paint(note))
+    } else {
+        write!(w, "{note}")
     }
 }
 
@@ -135,23 +258,27 @@ fn color_item_token(token: &Token, bg: Option<Color>) -> AnsiString<'_> {
 
 /// Returns a styled string similar to `color_item_token`, but where whole tokens are highlighted if
 /// they contain a difference.
-fn color_item_with_diff(diff_slice: &[diff::Result<&&Token>], is_old_item: bool) -> String {
-    let styled_strings = diff_slice
+fn color_item_with_diff(token_diff: &[TokenRun<'_>], is_old_item: bool) -> String {
+    let styled_strings = token_diff
         .iter()
-        .filter_map(|diff_result| match *diff_result {
-            diff::Result::Left(&token) => is_old_item.then(|| {
-                Color::Fixed(9)
-                    .on(Color::Fixed(52))
-                    .bold()
-                    .paint(token.text())
-            }),
-            diff::Result::Both(&token, _) => Some(color_item_token(token, None)),
-            diff::Result::Right(&token) => (!is_old_item).then(|| {
-                Color::Fixed(10)
-                    .on(Color::Fixed(22))
-                    .bold()
-                    .paint(token.text())
-            }),
+        .flat_map(|run| {
+            let highlight = match run {
+                TokenRun::Common(_) => None,
+                TokenRun::Removed(_) if is_old_item => {
+                    Some(Color::Fixed(9).on(Color::Fixed(52)).bold())
+                }
+                TokenRun::Inserted(_) if !is_old_item => {
+                    Some(Color::Fixed(10).on(Color::Fixed(22)).bold())
+                }
+                TokenRun::Removed(_) | TokenRun::Inserted(_) => return vec![],
+            };
+            run.tokens()
+                .iter()
+                .map(|token| match highlight {
+                    Some(style) => style.paint(token.text()),
+                    None => color_item_token(token, None),
+                })
+                .collect()
         })
         .collect::<Vec<_>>();
 
@@ -4,43 +4,219 @@
 
 use crate::{Args, ArgsAndToolchain, LATEST_VERSION_ARG};
 use anyhow::{Context, Result, anyhow};
-use crates_index::{Crate, Version};
+use crates_index::Crate;
 use std::path::PathBuf;
 
+/// The name given to the registry of `diff --index`, so that the dummy project
+/// can depend on a crate from it.
+const INDEX_REGISTRY_NAME: &str = "public-api-index";
+
+/// A published version of a crate.
+struct PublishedVersion {
+    name: String,
+    version: String,
+    features: Vec<String>,
+}
+
+impl From<&crates_index::Version> for PublishedVersion {
+    fn from(version: &crates_index::Version) -> Self {
+        Self {
+            name: version.name().to_owned(),
+            version: version.version().to_owned(),
+            features: version.features().keys().cloned().collect(),
+        }
+    }
+}
+
+/// Where published crates are downloaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Registry {
+    CratesIo,
+
+    /// An alternative registry configured in `.cargo/config.toml`, from
+    /// `diff --registry`.
+    Named(String),
+
+    /// A registry index URL, from `diff --index`.
+    Index(String),
+}
+
+impl std::fmt::Display for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CratesIo => write!(f, "crates.io"),
+            Self::Named(name) => write!(f, "registry `{name}`"),
+            Self::Index(url) => write!(f, "index `{url}`"),
+        }
+    }
+}
+
+impl Registry {
+    fn from_args(args: &Args) -> Self {
+        let diff_args = args.diff_args();
+        if let Some(name) = diff_args.and_then(|a| a.registry.clone()) {
+            Self::Named(name)
+        } else if let Some(url) = diff_args.and_then(|a| a.index.clone()) {
+            Self::Index(url)
+        } else {
+            Self::CratesIo
+        }
+    }
+
+    /// The `registry` of the dependency of the dummy project.
+    fn dependency_registry(&self) -> Option<String> {
+        match self {
+            Self::CratesIo => None,
+            Self::Named(name) => Some(name.clone()),
+            Self::Index(_) => Some(INDEX_REGISTRY_NAME.to_owned()),
+        }
+    }
+}
+
+/// Environment variables for cargo, so that it finds the registry and stays
+/// offline with `diff --offline`.
+fn cargo_envs(args: &Args) -> Vec<(String, String)> {
+    let mut envs = vec![];
+    if let Registry::Index(url) = Registry::from_args(args) {
+        let name = INDEX_REGISTRY_NAME.to_uppercase().replace('-', "_");
+        envs.push((format!("CARGO_REGISTRIES_{name}_INDEX"), url));
+    }
+    if offline(args) {
+        envs.push(("CARGO_NET_OFFLINE".to_owned(), "true".to_owned()));
+    }
+    envs
+}
+
+fn offline(args: &Args) -> bool {
+    args.diff_args().is_some_and(|a| a.offline)
+}
+
 pub fn build_rustdoc_json(version: Option<&str>, argst: &ArgsAndToolchain) -> Result<PathBuf> {
     let args = &argst.args;
-    let package_name = package_name_from_args(args).ok_or_else(|| anyhow!("You must specify a package with either `-p package-name` or `--manifest-path path/to/Cargo.toml`"))?;
-    let crate_ = http_get_crate(&package_name, args.debug_processing)?;
-    let crate_version = get_crate_version(&crate_, version)?;
-    let build_dir = build_dir(args, &crate_version);
-    std::fs::create_dir_all(&build_dir)?;
-
-    let write_file = |name: &str, contents: &str| -> std::io::Result<PathBuf> {
-        let mut path = build_dir.clone();
-        path.push(name);
-        std::fs::write(&path, contents)?;
-        Ok(path)
-    };
+    let published = published_version(version, args)?;
+    let source = format!(
+        "{} {} from {}",
+        published.name,
+        published.version,
+        Registry::from_args(args)
+    );
+    crate::cache::rustdoc_json(argst, &source, || {
+        build_rustdoc_json_for_version(argst, &published)
+    })
+}
 
-    write_file("lib.rs", "// empty lib")?;
-    let manifest = manifest_for(args, &crate_version)?;
-    let manifest = write_file("Cargo.toml", &manifest)?;
+fn build_rustdoc_json_for_version(
+    argst: &ArgsAndToolchain,
+    published: &PublishedVersion,
+) -> Result<PathBuf> {
+    let args = &argst.args;
+    let manifest = write_dummy_project(args, published, &format!("={}", published.version))?;
 
     // Since we used `crate::builder_from_args(args)` above it means that if
     // `args.target_dir` is set, both the dummy crate and the real crate will
     // write to the same JSON path since they have the same project name! That
     // won't work. So always clear the target dir before we use the builder.
-    let builder = crate::api_source::builder_from_args(argst)
+    let mut builder = crate::api_source::builder_from_args(argst)
         .clear_target_dir()
         .all_features(false)
         .features(Vec::<&str>::new())
         .no_default_features(false)
         .manifest_path(manifest)
-        .package(crate_version.name());
+        .package(&published.name);
+    for (key, value) in cargo_envs(args) {
+        builder = builder.env(key, value);
+    }
     crate::api_source::build_rustdoc_json(builder)
 }
 
-fn get_crate_version(crate_: &Crate, version: Option<&str>) -> Result<Version, anyhow::Error> {
+/// Writes a dummy project that depends on the crate of `published` with the
+/// version requirement `version_req`, and returns the path to its manifest.
+fn write_dummy_project(
+    args: &Args,
+    published: &PublishedVersion,
+    version_req: &str,
+) -> Result<PathBuf> {
+    let build_dir = build_dir(args, &published.name, &published.version);
+    std::fs::create_dir_all(&build_dir)?;
+
+    let write_file = |name: &str, contents: &str| -> std::io::Result<PathBuf> {
+        let mut path = build_dir.clone();
+        path.push(name);
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    };
+
+    write_file("lib.rs", "// empty lib")?;
+    let manifest = manifest_for(args, published, version_req)?;
+    Ok(write_file("Cargo.toml", &manifest)?)
+}
+
+/// Resolves `version` to an exact published version. For example, `None` and
+/// `Some("latest")` resolves to the latest published version.
+pub fn resolve_version(version: Option<&str>, args: &Args) -> Result<semver::Version> {
+    let published = published_version(version, args)?;
+    Ok(semver::Version::parse(&published.version)?)
+}
+
+/// Looks up `version` of the crate in the crates.io index. For other
+/// registries, and offline, we let cargo look it up instead, since cargo knows
+/// how to reach them from its config.
+fn published_version(version: Option<&str>, args: &Args) -> Result<PublishedVersion> {
+    let registry = Registry::from_args(args);
+    if registry == Registry::CratesIo && !offline(args) {
+        let crate_ = http_get_crate(&package_name(args)?, args.debug_processing)?;
+        let crate_version = get_crate_version(&crate_, version)?;
+        return Ok(PublishedVersion::from(&crate_version));
+    }
+
+    let name = package_name(args)?;
+    let (version_req, resolved) = match version {
+        Some(LATEST_VERSION_ARG) | None => ("*".to_owned(), "resolve-latest".to_owned()),
+        Some(version) => (format!("={version}"), version.to_owned()),
+    };
+    let unresolved = PublishedVersion {
+        name: name.clone(),
+        version: resolved,
+        features: vec![],
+    };
+    let manifest = write_dummy_project(args, &unresolved, &version_req)?;
+    if version_req == "*" {
+        // Otherwise the version locked by an earlier run would be used
+        let _ = std::fs::remove_file(manifest.with_file_name("Cargo.lock"));
+    }
+
+    let mut metadata = cargo_metadata::MetadataCommand::new();
+    metadata.manifest_path(&manifest);
+    for (key, value) in cargo_envs(args) {
+        metadata.env(key, value);
+    }
+    let package = metadata
+        .exec()
+        .with_context(|| format!("Failed to get `{name}` from {registry}"))?
+        .packages
+        .into_iter()
+        .find(|package| package.name.as_str() == name)
+        .ok_or_else(|| anyhow!("Could not find crate `{name}` in {registry}"))?;
+
+    if !matches!(version, Some(v) if v != LATEST_VERSION_ARG) {
+        let resolved = if version.is_none() {
+            "diff"
+        } else {
+            "diff latest"
+        };
+        eprintln!("Resolved `{resolved}` to `diff {}`", package.version);
+    }
+    Ok(PublishedVersion {
+        name,
+        version: package.version.to_string(),
+        features: package.features.into_keys().collect(),
+    })
+}
+
+fn get_crate_version(
+    crate_: &Crate,
+    version: Option<&str>,
+) -> Result<crates_index::Version, anyhow::Error> {
     match version {
         Some(LATEST_VERSION_ARG) | None => {
             let resolved = if version.is_none() {
@@ -70,6 +246,12 @@ fn get_crate_version(crate_: &Crate, version: Option<&str>) -> Result<Version, a
     }
 }
 
+/// Like [`package_name_from_args()`] but with a helpful error if there is no
+/// package name.
+pub fn package_name(args: &Args) -> Result<String> {
+    package_name_from_args(args).ok_or_else(|| anyhow!("You must specify a package with either `-p package-name` or `--manifest-path path/to/Cargo.toml`"))
+}
+
 /// Returns the package name from `-p package-name` or from inside
 /// `--manifest-path Cargo.toml`.
 fn package_name_from_args(args: &Args) -> Option<String> {
@@ -84,7 +266,7 @@ fn package_name_from_args(args: &Args) -> Option<String> {
 /// For users we prefer a non-temporary dir so repeated builds can be
 /// incremental. But when tests run, they will set `args.target_dir` to a
 /// temporary dir so that tests can run in parallel without interference.
-fn build_dir(args: &Args, crate_version: &crates_index::Version) -> PathBuf {
+fn build_dir(args: &Args, name: &str, version: &str) -> PathBuf {
     let mut build_dir = if let Some(target_dir) = &args.target_dir {
         target_dir.clone()
     } else {
@@ -93,15 +275,15 @@ fn build_dir(args: &Args, crate_version: &crates_index::Version) -> PathBuf {
 
     build_dir.push("cargo-public-api");
     build_dir.push("build-root-for-published-crates");
-    build_dir.push(crate_version.name());
+    build_dir.push(name);
     build_dir.push("-");
-    build_dir.push(crate_version.version());
+    build_dir.push(version);
     build_dir
 }
 
 /// Creates a manifest with a dependency so we can "trick" cargo into
 /// downloading the dependency for us.
-fn manifest_for(args: &Args, spec: &crates_index::Version) -> Result<String> {
+fn manifest_for(args: &Args, published: &PublishedVersion, version_req: &str) -> Result<String> {
     let setup = toml::toml! {
         [package]
         name = "crate-downloader"
@@ -120,12 +302,13 @@ fn manifest_for(args: &Args, spec: &crates_index::Version) -> Result<String> {
 
     Ok(format!(
         "{setup}\n[dependencies.{}]\n{}",
-        spec.name(),
+        published.name,
         toml::to_string(&cargo_manifest::DependencyDetail {
-            version: Some(format!("={}", spec.version())),
+            version: Some(version_req.to_owned()),
+            registry: Registry::from_args(args).dependency_registry(),
             default_features: no_default_features.then(|| false),
             features: if *all_features {
-                Some(spec.features().keys().map(Clone::clone).collect())
+                Some(published.features.clone())
             } else if !features.is_empty() {
                 Some(features.clone())
             } else {
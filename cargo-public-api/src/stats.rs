@@ -0,0 +1,195 @@
+//! Summarizes a public API for `cargo public-api stats`, e.g. to track how
+//! the public API grows from release to release.
+
+use std::collections::{BTreeMap, HashSet};
+
+use public_api::{ItemKind, PublicApi, PublicItem, tokens::Token};
+
+/// Items with at least this many distinct generic parameters in their
+/// rendered signature are counted as generic-heavy.
+const GENERIC_HEAVY_THRESHOLD: usize = 3;
+
+/// A group of counts, such as the number of items per kind.
+pub struct Section {
+    /// Used as the key in JSON output, e.g. `kinds`.
+    pub key: &'static str,
+
+    /// Used as the header in plain and Markdown output.
+    pub title: &'static str,
+
+    pub rows: Vec<Row>,
+}
+
+/// One count in a [`Section`], e.g. the number of methods.
+pub struct Row {
+    /// E.g. `method` or `my_crate::client`.
+    pub label: String,
+
+    /// The count in the old public API, if two public APIs are compared.
+    pub old: Option<usize>,
+
+    pub new: usize,
+}
+
+impl Row {
+    /// How much the count changed, if two public APIs are compared.
+    pub fn delta(&self) -> Option<i64> {
+        self.old.map(|old| self.new as i64 - old as i64)
+    }
+}
+
+/// Summarizes `new`, and compares it with `old` if given. Re-export aliases
+/// are only counted in the summary, so that re-exported items are not counted
+/// twice.
+pub fn stats(old: Option<&PublicApi>, new: &PublicApi) -> Vec<Section> {
+    let new = counts(new);
+    let Some(old) = old.map(counts) else {
+        return new
+            .into_iter()
+            .map(|(key, title, counts)| Section {
+                key,
+                title,
+                rows: counts
+                    .into_iter()
+                    .map(|(label, new)| Row {
+                        label,
+                        old: None,
+                        new,
+                    })
+                    .collect(),
+            })
+            .collect();
+    };
+
+    new.into_iter()
+        .zip(old)
+        .map(|((key, title, new), (_, _, old))| {
+            let mut labels: Vec<String> = new.iter().map(|(label, _)| label.clone()).collect();
+            for (label, _) in &old {
+                if !labels.contains(label) {
+                    labels.push(label.clone());
+                }
+            }
+            // The summary has a fixed order, the other sections are sorted
+            if key != "summary" {
+                labels.sort();
+            }
+
+            let count = |counts: &[(String, usize)], label: &str| {
+                counts
+                    .iter()
+                    .find_map(|(l, count)| (l == label).then_some(*count))
+                    .unwrap_or_default()
+            };
+            let rows = labels
+                .into_iter()
+                .map(|label| Row {
+                    old: Some(count(&old, &label)),
+                    new: count(&new, &label),
+                    label,
+                })
+                .collect();
+            Section { key, title, rows }
+        })
+        .collect()
+}
+
+type Counts = Vec<(String, usize)>;
+
+fn counts(api: &PublicApi) -> Vec<(&'static str, &'static str, Counts)> {
+    let items: Vec<&PublicItem> = api.items().filter(|item| !item.is_reexport()).collect();
+
+    let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+    let mut impls: BTreeMap<String, usize> = BTreeMap::new();
+    for item in &items {
+        match item.kind() {
+            Some(kind @ ItemKind::Impl(_)) => *impls.entry(kind.to_string()).or_default() += 1,
+            Some(kind) => *kinds.entry(kind.to_string()).or_default() += 1,
+            None => *kinds.entry("unknown".to_owned()).or_default() += 1,
+        }
+    }
+
+    let summary = vec![
+        ("items".to_owned(), items.len()),
+        (
+            "re-export aliases".to_owned(),
+            api.items().filter(|item| item.is_reexport()).count(),
+        ),
+        (
+            "generic-heavy items".to_owned(),
+            items.iter().filter(|item| is_generic_heavy(item)).count(),
+        ),
+        (
+            "unsafe functions".to_owned(),
+            items.iter().filter(|item| is_unsafe_function(item)).count(),
+        ),
+        (
+            "non-exhaustive types".to_owned(),
+            items
+                .iter()
+                .filter(|item| is_non_exhaustive_type(item))
+                .count(),
+        ),
+    ];
+
+    vec![
+        ("summary", "Summary", summary),
+        ("kinds", "Items per kind", kinds.into_iter().collect()),
+        ("impls", "Impls per kind", impls.into_iter().collect()),
+        ("modules", "Items per module", items_per_module(&items)),
+    ]
+}
+
+/// Counts the items in each module, not including items in submodules. A
+/// module itself is counted in its parent module.
+fn items_per_module(items: &[&PublicItem]) -> Counts {
+    let modules: HashSet<&[String]> = items
+        .iter()
+        .filter(|item| item.kind() == Some(ItemKind::Module))
+        .map(|item| item.path())
+        .collect();
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for item in items {
+        let path = item.path();
+        // A module is not in itself, and neither is a type in its own impls
+        let parents = if item.kind() == Some(ItemKind::Module) {
+            path.len().saturating_sub(1)
+        } else {
+            path.len()
+        };
+        if let Some(module) = (0..parents)
+            .rev()
+            .map(|len| &path[..len])
+            .find(|prefix| modules.contains(prefix))
+        {
+            *counts.entry(module.join("::")).or_default() += 1;
+        }
+    }
+    counts.into_iter().collect()
+}
+
+fn is_generic_heavy(item: &PublicItem) -> bool {
+    let generics: HashSet<&str> = item
+        .tokens()
+        .filter(|token| matches!(token, Token::Generic(_)))
+        .map(Token::text)
+        .collect();
+    generics.len() >= GENERIC_HEAVY_THRESHOLD
+}
+
+fn is_unsafe_function(item: &PublicItem) -> bool {
+    matches!(item.kind(), Some(ItemKind::Function | ItemKind::Method))
+        && item
+            .tokens()
+            .any(|token| matches!(token, Token::Qualifier(q) if q == "unsafe"))
+}
+
+fn is_non_exhaustive_type(item: &PublicItem) -> bool {
+    matches!(
+        item.kind(),
+        Some(ItemKind::Struct | ItemKind::Enum | ItemKind::Union)
+    ) && item
+        .tokens()
+        .any(|token| matches!(token, Token::Annotation(a) if a == "#[non_exhaustive]"))
+}
@@ -0,0 +1,139 @@
+//! Lists or diffs the public API for several target triples at once, for
+//! `--targets`, so that items and changes that only apply to some targets
+//! stand out.
+
+use anyhow::{Context, Result};
+use public_api::diff::PublicApiDiff;
+use public_api::{PublicApi, PublicItem};
+
+use crate::ArgsAndToolchain;
+use crate::api_source::ApiSource;
+use crate::merged_api::MergedApi;
+use crate::merged_diff::MergedDiff;
+
+impl ArgsAndToolchain {
+    /// The args to use to build for `target`, regardless of any `--target`
+    /// given by the user.
+    pub fn with_target(&self, target: &str) -> Self {
+        let mut args = self.args.clone();
+        args.target = Some(target.to_owned());
+        Self {
+            args,
+            toolchain: self.toolchain.clone(),
+        }
+    }
+}
+
+/// The target triples given with `--targets`, without duplicates.
+pub fn targets(argst: &ArgsAndToolchain) -> Vec<String> {
+    let mut targets: Vec<String> = vec![];
+    for target in &argst.args.targets {
+        if !targets.contains(target) {
+            targets.push(target.clone());
+        }
+    }
+    targets
+}
+
+/// Builds the public API of `api` for each of the `targets`, and merges them.
+/// Items that are not part of the public API on all targets are returned with
+/// a `cfg`-like condition with the targets they are part of, e.g.
+/// `target = "x86_64-pc-windows-msvc"`.
+pub fn public_api_with_targets(
+    argst: &ArgsAndToolchain,
+    api: &dyn ApiSource,
+    targets: &[String],
+) -> Result<Vec<(PublicItem, Option<String>)>> {
+    let apis = targets
+        .iter()
+        .map(|target| {
+            api.obtain_api(&argst.with_target(target))
+                .with_context(|| format!("Failed to build the public API for target `{target}`"))
+        })
+        .collect::<Result<Vec<PublicApi>>>()?;
+
+    Ok(MergedApi::new(apis)
+        .items
+        .into_iter()
+        .map(|merged| {
+            let cfg = target_cfg(targets, &merged.present_in);
+            (merged.item, cfg)
+        })
+        .collect())
+}
+
+/// Diffs `old` and `new` for each of the `targets`, and merges the diffs.
+pub fn diff_with_targets(
+    argst: &ArgsAndToolchain,
+    old: &dyn ApiSource,
+    new: &dyn ApiSource,
+    targets: &[String],
+) -> Result<MergedDiff> {
+    let diffs = targets
+        .iter()
+        .map(|target| {
+            crate::diff_apis(&argst.with_target(target), old, new)
+                .with_context(|| format!("Failed to diff the public API for target `{target}`"))
+        })
+        .collect::<Result<Vec<PublicApiDiff>>>()?;
+
+    Ok(MergedDiff::new(diffs))
+}
+
+/// Describes the targets of `present_in`, e.g. `target
+/// x86_64-unknown-linux-gnu`.
+pub fn describe(targets: &[String], present_in: &[usize]) -> String {
+    if present_in.len() == targets.len() && targets.len() > 1 {
+        return "all targets".to_owned();
+    }
+    let targets: Vec<&str> = present_in.iter().map(|&i| targets[i].as_str()).collect();
+    match targets.as_slice() {
+        [target] => format!("target `{target}`"),
+        _ => format!("targets `{}`", targets.join("`, `")),
+    }
+}
+
+/// The condition for an item that is part of the public API on the targets of
+/// `present_in`, or `None` if it is part of it on all targets.
+fn target_cfg(targets: &[String], present_in: &[usize]) -> Option<String> {
+    if present_in.len() == targets.len() {
+        return None;
+    }
+    let conditions: Vec<String> = present_in
+        .iter()
+        .map(|&i| format!("target = {:?}", targets[i]))
+        .collect();
+    Some(match conditions.as_slice() {
+        [condition] => condition.clone(),
+        _ => format!("any({})", conditions.join(", ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_cfgs() {
+        let targets = [
+            "x86_64-unknown-linux-gnu".to_owned(),
+            "x86_64-pc-windows-msvc".to_owned(),
+            "wasm32-unknown-unknown".to_owned(),
+        ];
+
+        assert_eq!(target_cfg(&targets, &[0, 1, 2]), None);
+        assert_eq!(
+            target_cfg(&targets, &[1]).unwrap(),
+            r#"target = "x86_64-pc-windows-msvc""#
+        );
+        assert_eq!(
+            target_cfg(&targets, &[0, 2]).unwrap(),
+            r#"any(target = "x86_64-unknown-linux-gnu", target = "wasm32-unknown-unknown")"#
+        );
+        assert_eq!(describe(&targets, &[0, 1, 2]), "all targets");
+        assert_eq!(
+            describe(&targets, &[0, 1]),
+            "targets `x86_64-unknown-linux-gnu`, `x86_64-pc-windows-msvc`"
+        );
+    }
+}
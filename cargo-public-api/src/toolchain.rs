@@ -40,3 +40,25 @@ pub fn from_rustup() -> Option<String> {
             }
         })
 }
+
+/// Returns e.g. `rustc 1.92.0-nightly (4068bafed 2025-09-20)` for the given
+/// toolchain, or the active toolchain if `None`.
+pub fn rustc_version(toolchain: Option<&str>) -> Option<String> {
+    let mut cmd = match toolchain {
+        Some(toolchain) => {
+            let mut cmd = std::process::Command::new("rustup");
+            cmd.args(["run", toolchain, "rustc"]);
+            cmd
+        }
+        None => std::process::Command::new("rustc"),
+    };
+    cmd.arg("--version");
+
+    let output = cmd.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|version| version.trim().to_owned())
+}
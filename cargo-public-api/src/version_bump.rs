@@ -0,0 +1,118 @@
+//! Helpers for `cargo public-api diff --required-bump`, which suggests the
+//! next version of a crate based on how its public API changed since it was
+//! last published.
+
+use anyhow::{Context, Result, anyhow};
+use public_api::semver::SemverImpact;
+use semver::Version;
+
+use crate::Args;
+
+/// Returns the smallest version after `published` that is compatible with
+/// `impact`, following the same rules as cargo: for `0.y.z` versions, a bump
+/// of `y` is a breaking change, and for `0.0.z` versions, every bump is a
+/// breaking change.
+///
+/// If `published` is a pre-release, e.g. `1.1.0-alpha.1`, its release `1.1.0`
+/// is suggested, unless `impact` needs more than the release is a bump of.
+pub fn next_version(published: &Version, impact: SemverImpact) -> Version {
+    if !published.pre.is_empty() {
+        let release = Version::new(published.major, published.minor, published.patch);
+        return if impact <= impact_of_release(&release) {
+            release
+        } else {
+            next_version(&release, impact)
+        };
+    }
+
+    let mut next = Version::new(published.major, published.minor, published.patch);
+    match (published.major, published.minor, impact) {
+        (0, 0, _)
+        | (0, _, SemverImpact::Minor | SemverImpact::Patch)
+        | (_, _, SemverImpact::Patch) => {
+            next.patch += 1;
+        }
+        (0, _, SemverImpact::Major) | (_, _, SemverImpact::Minor) => {
+            next.minor += 1;
+            next.patch = 0;
+        }
+        (_, _, SemverImpact::Major) => {
+            next.major += 1;
+            next.minor = 0;
+            next.patch = 0;
+        }
+    }
+    next
+}
+
+/// The most severe impact that `release` allows compared to the release before
+/// it, e.g. [`SemverImpact::Minor`] for `1.1.0` and [`SemverImpact::Major`]
+/// for `0.4.0`.
+fn impact_of_release(release: &Version) -> SemverImpact {
+    match (release.major, release.minor, release.patch) {
+        (0, 0, _) | (0, _, 0) | (_, 0, 0) => SemverImpact::Major,
+        // For `0.y.z` versions, a bump of `z` is not breaking
+        (0, _, _) | (_, _, 0) => SemverImpact::Minor,
+        _ => SemverImpact::Patch,
+    }
+}
+
+/// Returns the version in `Cargo.toml` of the package we list or diff the
+/// public API for. Uses `cargo metadata` so that e.g. `version.workspace =
+/// true` is resolved.
+pub fn manifest_version(args: &Args) -> Result<Version> {
+    let package_name = crate::published_crate::package_name(args)?;
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(&args.manifest_path)
+        .no_deps()
+        .exec()
+        .with_context(|| {
+            format!(
+                "Failed to run `cargo metadata` for {:?}",
+                args.manifest_path
+            )
+        })?;
+
+    metadata
+        .packages
+        .into_iter()
+        .find(|package| package.name.as_str() == package_name)
+        .map(|package| package.version)
+        .ok_or_else(|| anyhow!("Could not find package `{package_name}` in the workspace"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_version() {
+        for (published, impact, expected) in [
+            ("1.2.3", SemverImpact::Major, "2.0.0"),
+            ("1.2.3", SemverImpact::Minor, "1.3.0"),
+            ("1.2.3", SemverImpact::Patch, "1.2.4"),
+            ("0.4.2", SemverImpact::Major, "0.5.0"),
+            ("0.4.2", SemverImpact::Minor, "0.4.3"),
+            ("0.4.2", SemverImpact::Patch, "0.4.3"),
+            ("0.0.7", SemverImpact::Major, "0.0.8"),
+            ("0.0.7", SemverImpact::Minor, "0.0.8"),
+            ("0.0.7", SemverImpact::Patch, "0.0.8"),
+            ("1.0.0-alpha.1", SemverImpact::Major, "1.0.0"),
+            ("1.0.0-alpha.1", SemverImpact::Patch, "1.0.0"),
+            ("1.2.0-rc.1", SemverImpact::Major, "2.0.0"),
+            ("1.2.0-rc.1", SemverImpact::Minor, "1.2.0"),
+            ("1.2.3-beta", SemverImpact::Minor, "1.3.0"),
+            ("1.2.3-beta", SemverImpact::Patch, "1.2.3"),
+            ("0.5.0-alpha", SemverImpact::Major, "0.5.0"),
+            ("0.4.3-alpha", SemverImpact::Major, "0.5.0"),
+            ("0.4.3-alpha", SemverImpact::Minor, "0.4.3"),
+            ("0.0.7-alpha", SemverImpact::Major, "0.0.7"),
+        ] {
+            assert_eq!(
+                next_version(&Version::parse(published).unwrap(), impact),
+                Version::parse(expected).unwrap(),
+                "{published} with a {impact} change",
+            );
+        }
+    }
+}
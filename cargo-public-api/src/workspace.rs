@@ -0,0 +1,178 @@
+//! Lists or diffs the public API of all library packages in a workspace, for
+//! `--workspace`.
+
+use std::io::{Write, stdout};
+
+use anyhow::{Context, Result, bail};
+use cargo_metadata::Target;
+
+use crate::allowlist::StaleEntries;
+use crate::json::{Json, JsonPackage};
+use crate::plain::print_items_with_header;
+use crate::{Args, ArgsAndToolchain, Outcome, OutputFormat, error};
+
+impl ArgsAndToolchain {
+    /// The args to use for one package of the workspace.
+    fn for_package(&self, package: &str) -> Self {
+        let mut args = self.args.clone();
+        args.package = Some(package.to_owned());
+        args.workspace = false;
+        Self {
+            args,
+            toolchain: self.toolchain.clone(),
+        }
+    }
+}
+
+/// Runs the main task for each library package in the workspace. A failure,
+/// such as a diff not allowed by `--deny`, does not stop the remaining
+/// packages from being processed, but makes the whole run fail at the end.
+pub fn run_for_workspace(argst: &ArgsAndToolchain) -> Result<()> {
+    let packages = library_packages(&argst.args)?;
+
+    let mut results = vec![];
+    let mut json_packages = vec![];
+    let mut stale_entries = StaleEntries::default();
+    for package in packages {
+        let mut output = vec![];
+        let result = crate::run(
+            &argst.for_package(&package),
+            &mut output,
+            &mut stale_entries,
+        );
+
+        match argst.args.output_format {
+            OutputFormat::Plain | OutputFormat::Markdown => {
+                let mut w = stdout();
+                let header = format!("Package {package}");
+                writeln!(w, "{header}")?;
+                writeln!(w, "{}", "=".repeat(header.len()))?;
+                w.write_all(&output)?;
+                // Diffs already end with an empty line, listings do not
+                if !output.ends_with(b"\n\n") {
+                    writeln!(w)?;
+                }
+            }
+            OutputFormat::Json => json_packages.push(JsonPackage {
+                name: package.clone(),
+                output: serde_json::from_slice(&output).ok(),
+                error: result.as_ref().err().map(summarize_error),
+            }),
+        }
+
+        results.push((package, result));
+    }
+
+    match argst.args.output_format {
+        OutputFormat::Plain => print_summary(&mut stdout(), "", &results)?,
+        // Without a list marker, Markdown would join the lines to one paragraph
+        OutputFormat::Markdown => print_summary(&mut stdout(), "- ", &results)?,
+        OutputFormat::Json => Json::print_workspace(&mut stdout(), json_packages)?,
+    }
+    stale_entries.warn();
+
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    if failed > 0 {
+        bail!("{failed} of {} packages failed", results.len());
+    }
+
+    Ok(())
+}
+
+/// Names of all packages in the workspace that have a library target, except
+/// the ones excluded with `--exclude`. Sorted to get a stable output.
+fn library_packages(args: &Args) -> Result<Vec<String>> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(&args.manifest_path)
+        .no_deps()
+        .exec()
+        .with_context(|| {
+            format!(
+                "Failed to run `cargo metadata` for {:?}",
+                args.manifest_path
+            )
+        })?;
+    let members = metadata.workspace_packages();
+
+    for excluded in &args.exclude {
+        if !members.iter().any(|p| p.name.as_str() == excluded) {
+            bail!("Excluded package `{excluded}` is not a member of the workspace");
+        }
+    }
+
+    let mut packages: Vec<String> = members
+        .into_iter()
+        .filter(|p| p.targets.iter().any(is_library))
+        .map(|p| p.name.to_string())
+        .filter(|name| !args.exclude.contains(name))
+        .collect();
+    packages.sort();
+
+    if packages.is_empty() {
+        bail!("Found no library packages in the workspace");
+    }
+
+    Ok(packages)
+}
+
+fn is_library(target: &Target) -> bool {
+    target.is_lib() || target.is_rlib() || target.is_dylib() || target.is_proc_macro()
+}
+
+fn print_summary(
+    w: &mut dyn Write,
+    prefix: &str,
+    results: &[(String, Result<Outcome>)],
+) -> Result<()> {
+    print_items_with_header(w, "Summary", results, |w, (package, result)| {
+        write!(w, "{prefix}")?;
+        print_outcome(w, package, result)
+    })?;
+    Ok(())
+}
+
+fn print_outcome(
+    w: &mut dyn Write,
+    package: &str,
+    result: &Result<Outcome>,
+) -> std::io::Result<()> {
+    match result {
+        Ok(Outcome::Listed { items }) => writeln!(w, "{package}: {items} items"),
+        Ok(Outcome::Diffed {
+            removed,
+            changed,
+            deprecated,
+            moved,
+            added,
+        }) => {
+            write!(
+                w,
+                "{package}: {removed} removed, {changed} changed, {added} added"
+            )?;
+            if *deprecated > 0 {
+                write!(w, ", {deprecated} deprecated")?;
+            }
+            if *moved > 0 {
+                write!(w, ", {moved} moved")?;
+            }
+            writeln!(w)
+        }
+        Ok(Outcome::Other) => writeln!(w, "{package}: ok"),
+        Err(e) => writeln!(w, "{package}: failed: {}", summarize_error(e)),
+    }
+}
+
+/// A one line description of why a package failed. Items not allowed by
+/// `--deny` are already visible in the diff of the package, so we do not
+/// repeat them.
+fn summarize_error(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<error::Error>() {
+        Some(error::Error::DiffDenied(_)) => "The API diff is not allowed as per --deny".to_owned(),
+        _ => error
+            .to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned(),
+    }
+}
@@ -0,0 +1,93 @@
+//! Temporary `git worktree`s to build commits in, for `diff --worktree`. Unlike
+//! an in-place `git checkout`, this leaves the checkout of the user untouched,
+//! even if we crash, and it works with local changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::{ArgsAndToolchain, git_utils};
+
+/// `git worktree add` and `git worktree remove` lock the repo, so we run them
+/// one at a time even when commits are built in parallel.
+static GIT_WORKTREE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Makes worktree dirs unique even if the same commit is built twice.
+static WORKTREE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary worktree with a commit checked out. Removed when dropped.
+pub struct Worktree {
+    git_root: PathBuf,
+    dir: PathBuf,
+}
+
+impl Worktree {
+    /// Adds a worktree with `commit` checked out. With `--init-submodules`,
+    /// submodules are initialized too.
+    pub fn new(argst: &ArgsAndToolchain, commit: &str) -> Result<Self> {
+        let args = &argst.args;
+        let git_root = args.git_root()?;
+        let dir = std::env::temp_dir()
+            .join("cargo-public-api-worktrees")
+            .join(format!(
+                "{}-{}-{commit}",
+                std::process::id(),
+                WORKTREE_COUNT.fetch_add(1, Ordering::Relaxed)
+            ));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove old worktree at {dir:?}"))?;
+        }
+
+        let quiet = !args.debug_processing;
+        {
+            let _lock = GIT_WORKTREE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            git_utils::git_worktree_add(&git_root, &dir, commit, quiet)?;
+        }
+        let worktree = Self { git_root, dir };
+
+        if args.diff_args().is_some_and(|a| a.init_submodules) {
+            git_utils::git_submodule_update_init(&worktree.dir, quiet)?;
+        }
+
+        Ok(worktree)
+    }
+
+    /// The args to build the package in the worktree instead of in the
+    /// checkout of the user. Each worktree gets its own target dir, so that
+    /// worktrees can be built in parallel.
+    pub fn argst(&self, argst: &ArgsAndToolchain) -> Result<ArgsAndToolchain> {
+        let mut args = argst.args.clone();
+        args.manifest_path = self
+            .dir
+            .join(path_in_repo(&self.git_root, &args.manifest_path)?);
+        if let Some(target_dir) = &args.target_dir {
+            let name = self.dir.file_name().expect("worktree dirs have a name");
+            args.target_dir = Some(target_dir.join("worktrees").join(name));
+        }
+        Ok(ArgsAndToolchain {
+            args,
+            toolchain: argst.toolchain.clone(),
+        })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let _lock = GIT_WORKTREE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = git_utils::git_worktree_remove(&self.git_root, &self.dir) {
+            eprintln!("Warning: Failed to remove worktree at {:?}: {e}", self.dir);
+        }
+    }
+}
+
+/// The path of `path` relative to the root of the git repo.
+fn path_in_repo(git_root: &Path, path: &Path) -> Result<PathBuf> {
+    let path =
+        std::fs::canonicalize(path).with_context(|| format!("Failed to canonicalize {path:?}"))?;
+    path.strip_prefix(git_root)
+        .map(Path::to_path_buf)
+        .map_err(|_| anyhow!("{path:?} is not in the git repo at {git_root:?}"))
+}
@@ -304,20 +304,6 @@ fn list_public_items_via_package_spec() {
 
 #[test]
 fn target_arg() {
-    // A bit of a hack but similar to how rustc bootstrap script does it:
-    // https://github.com/rust-lang/rust/blob/1ce51982b8550c782ded466c1abff0d2b2e21c4e/src/bootstrap/bootstrap.py#L207-L219
-    fn get_host_target_triple() -> String {
-        let mut cmd = std::process::Command::new("rustc");
-        cmd.arg("-vV");
-        let output = cmd.output().unwrap();
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        stdout
-            .lines()
-            .find_map(|line| line.strip_prefix("host: "))
-            .unwrap()
-            .to_owned()
-    }
-
     // Make sure to use a separate and temporary repo so that this test does not
     // accidentally pass due to files from other tests lying around
     let mut cmd = TestCmd::new().with_test_repo();
@@ -328,19 +314,111 @@ fn target_arg() {
         .success();
 }
 
+#[test]
+fn targets_arg() {
+    // Only the host target is installed when testing, so nothing is only part
+    // of the public API on some targets
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--targets");
+    cmd.arg(get_host_target_triple());
+    cmd.assert()
+        .stdout_with_insta("test_repo_api_latest")
+        .success();
+}
+
+#[test]
+fn diff_targets() {
+    let host = get_host_target_triple();
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["diff", "--targets", &host, "v0.1.0..v0.2.0"]);
+    cmd.assert()
+        .stdout(contains(format!("Diff with target `{host}`\n")))
+        .stdout(contains("+pub struct example_api::StructV2\n"))
+        .success();
+}
+
+#[test]
+fn targets_conflicts_with_target() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--target", "a", "--targets", "b,c"]);
+    cmd.assert()
+        .stderr(contains("cannot be used with"))
+        .failure();
+}
+
+// A bit of a hack but similar to how rustc bootstrap script does it:
+// https://github.com/rust-lang/rust/blob/1ce51982b8550c782ded466c1abff0d2b2e21c4e/src/bootstrap/bootstrap.py#L207-L219
+fn get_host_target_triple() -> String {
+    let mut cmd = std::process::Command::new("rustc");
+    cmd.arg("-vV");
+    let output = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .unwrap()
+        .to_owned()
+}
+
 #[test]
 fn virtual_manifest_error() {
     let mut cmd = TestCmd::new().with_separate_target_dir();
     cmd.arg("--manifest-path");
     cmd.arg("../test-apis/virtual-manifest/Cargo.toml");
+    cmd.assert()
+        .stdout("")
+        .stderr(contains("is a virtual manifest"))
+        .failure();
+}
+
+#[test]
+fn list_public_items_of_workspace() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/virtual-manifest");
+    cmd.arg("--workspace");
+    cmd.assert().stdout_with_insta("workspace").success();
+}
+
+#[test]
+fn list_public_items_of_workspace_with_exclude() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/virtual-manifest");
+    cmd.arg("--workspace");
+    cmd.arg("--exclude");
+    cmd.arg("other-crate");
+    cmd.assert()
+        .stdout(contains("Package specific-crate"))
+        .stdout(contains("other_crate").not())
+        .stdout(contains("specific-crate: 2 items"))
+        .success();
+}
+
+#[test]
+fn workspace_exclude_unknown_package() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/virtual-manifest");
+    cmd.arg("--workspace");
+    cmd.arg("--exclude");
+    cmd.arg("no-such-crate");
     cmd.assert()
         .stdout("")
         .stderr(contains(
-            "Listing or diffing the public API of an entire workspace is not supported.",
+            "Excluded package `no-such-crate` is not a member of the workspace",
         ))
         .failure();
 }
 
+#[test]
+fn workspace_conflicts_with_package() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--workspace");
+    cmd.arg("--package");
+    cmd.arg("specific-crate");
+    cmd.assert()
+        .stderr(contains("cannot be used with"))
+        .failure();
+}
+
 /// Make sure we can run the tool on the current directory as a cargo
 /// sub-command without any args
 #[test]
@@ -440,6 +518,49 @@ fn warn_when_using_beta() {
         .success();
 }
 
+#[test]
+fn rustdoc_json_is_cached() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    // Builds create an untracked `Cargo.lock`, which would prevent caching
+    let info = cmd.test_repo_path().join(".git/info");
+    std::fs::create_dir_all(&info).unwrap();
+    std::fs::write(info.join("exclude"), "Cargo.lock\n").unwrap();
+    cmd.args(["--debug-processing", "diff", "v0.1.0..v0.1.0"]);
+    cmd.assert()
+        .stderr(contains("Using cached rustdoc JSON"))
+        .success();
+}
+
+#[test]
+fn rustdoc_json_is_not_cached_with_untracked_files() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let untracked = cmd.test_repo_path().join("untracked.txt");
+    std::fs::write(untracked, "may affect the build").unwrap();
+    cmd.args(["--debug-processing", "diff", "v0.1.0..v0.1.0"]);
+    cmd.assert()
+        .stderr(contains("Using cached rustdoc JSON").not())
+        .success();
+}
+
+#[test]
+fn working_tree_is_not_cached() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--debug-processing");
+    cmd.assert().success();
+    cmd.assert()
+        .stderr(contains("Using cached rustdoc JSON").not())
+        .success();
+}
+
+#[test]
+fn rustdoc_json_is_not_cached_with_no_cache() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--debug-processing", "--no-cache", "diff", "v0.1.0..v0.1.0"]);
+    cmd.assert()
+        .stderr(contains("Using cached rustdoc JSON").not())
+        .success();
+}
+
 #[test]
 fn diff_public_items() {
     let mut cmd = TestCmd::new().with_test_repo();
@@ -642,6 +763,40 @@ fn diff_public_items_with_dirty_tree_succeeds_with_force_option() {
         .success();
 }
 
+/// Test that diffing in temporary worktrees leaves the checkout untouched, even
+/// if the git tree is dirty.
+#[test]
+fn diff_public_items_with_worktree() {
+    let test_repo = create_test_repo_with_dirty_git_tree();
+    let path = test_repo.path();
+    let branch_before = git_utils::current_branch(path).unwrap();
+    let lib_rs_before = std::fs::read_to_string(path.join("src/lib.rs")).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir(path);
+    cmd.arg("diff");
+    cmd.arg("--worktree");
+    cmd.arg("v0.2.0..v0.3.0");
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.2.0_to_v0.3.0")
+        .success();
+
+    assert_eq!(branch_before, git_utils::current_branch(path).unwrap());
+    assert_eq!(
+        lib_rs_before,
+        std::fs::read_to_string(path.join("src/lib.rs")).unwrap()
+    );
+    let worktrees = std::process::Command::new("git")
+        .current_dir(path)
+        .args(["worktree", "list"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&worktrees.stdout).lines().count(),
+        1
+    );
+}
+
 /// Test that relative git references like HEAD and HEAD^ work
 /// (even as the second diff target).
 #[test]
@@ -766,6 +921,141 @@ fn deny_removed_with_diff() {
         .failure();
 }
 
+#[test]
+fn deny_with_diff_accepted_by_allowlist() {
+    let allowlist = write_allowlist(
+        r#"
+[[accepted]]
+path = "example_api::function"
+reason = "The new parameter is needed"
+"#,
+    );
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg("v0.2.0..v0.3.0");
+    cmd.arg("--deny=removed");
+    cmd.arg("--allowlist");
+    cmd.arg(allowlist.path().join("allowlist.toml"));
+    cmd.assert()
+        .stderr(contains("Warning: Allowlist entry").not())
+        .success();
+}
+
+#[test]
+fn deny_with_diff_partially_accepted_by_allowlist() {
+    let allowlist = write_allowlist(
+        r#"
+[[accepted]]
+text = "pub struct example_api::StructV2"
+"#,
+    );
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg("v0.1.0..v0.2.0");
+    cmd.arg("--deny=added");
+    cmd.arg("--allowlist");
+    cmd.arg(allowlist.path().join("allowlist.toml"));
+    cmd.assert()
+        .stderr(contains(
+            "Added items not allowed: [pub example_api::Struct::v2_field: usize, pub example_api::StructV2::field: usize]",
+        ))
+        .failure();
+}
+
+#[test]
+fn deny_with_impl_not_accepted_by_path_allowlist_entry() {
+    let allowlist = write_allowlist(
+        r#"
+[[accepted]]
+path = "example_api::StructV2"
+
+[[accepted]]
+path = "example_api::StructV2::field"
+
+[[accepted]]
+path = "example_api::Struct::v2_field"
+"#,
+    );
+    // Without `--omit auto-trait-impls`, so that `impl`s are added too
+    let mut cmd = TestCmd::as_subcommand_without_args().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg("v0.1.0..v0.2.0");
+    cmd.arg("--deny=added");
+    cmd.arg("--allowlist");
+    cmd.arg(allowlist.path().join("allowlist.toml"));
+    cmd.assert()
+        .stderr(contains("Added items not allowed: [impl"))
+        .stderr(contains("core::marker::Send for example_api::StructV2"))
+        .stderr(contains("pub struct example_api::StructV2,").not())
+        .failure();
+}
+
+#[test]
+fn deny_with_stale_allowlist_entry() {
+    let allowlist = write_allowlist(
+        r#"
+[[accepted]]
+path = "example_api::function"
+"#,
+    );
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg("v0.1.0..v0.1.1");
+    cmd.arg("--deny=all");
+    cmd.arg("--allowlist");
+    cmd.arg(allowlist.path().join("allowlist.toml"));
+    cmd.assert()
+        .stderr(contains(
+            r#"Warning: Allowlist entry `path = "example_api::function"` does not match any change"#,
+        ))
+        .success();
+}
+
+#[test]
+fn deny_with_allowlist_for_workspace() {
+    let allowlist = write_allowlist(
+        r#"
+[[accepted]]
+path = "other_crate::other_crate"
+
+[[accepted]]
+path = "specific_crate::SpecificCrate"
+
+[[accepted]]
+path = "no_such_crate::function"
+"#,
+    );
+    let snapshot = allowlist.path().join("empty.txt");
+    std::fs::write(&snapshot, "").unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/virtual-manifest");
+    cmd.arg("--workspace");
+    cmd.arg("diff");
+    cmd.arg(&snapshot);
+    cmd.arg("--deny=removed");
+    cmd.arg("--allowlist");
+    cmd.arg(allowlist.path().join("allowlist.toml"));
+    // Entries that match a change in one of the packages are not stale, and
+    // stale entries are only warned about once
+    cmd.assert()
+        .stderr(contains("other_crate::other_crate\"` does not match").not())
+        .stderr(contains("SpecificCrate\"` does not match").not())
+        .stderr(contains(
+            r#"Warning: Allowlist entry `path = "no_such_crate::function"` does not match any change
+"#,
+        ))
+        .stderr(contains("no_such_crate::function").count(1))
+        .success();
+}
+
+/// Writes `allowlist.toml` with the given content to a new temporary dir.
+fn write_allowlist(content: &str) -> tempfile::TempDir {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("allowlist.toml"), content).unwrap();
+    dir
+}
+
 #[test]
 fn deny_with_invalid_arg() {
     let mut cmd = TestCmd::new().with_test_repo();
@@ -842,99 +1132,639 @@ fn list_public_items_with_color_impl(color_arg: &str) {
 }
 
 #[test]
-fn diff_public_items_from_files_with_subcommand() {
-    // Create independent build dirs so all tests can run in parallel
-    let build_dir = tempdir().unwrap();
-    let build_dir2 = tempdir().unwrap();
-
-    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
-    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
-    let mut cmd = TestCmd::new().with_separate_target_dir();
-    cmd.arg("diff");
-    cmd.arg(old);
-    cmd.arg(new);
+fn list_public_items_as_json() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["-sss", "--output-format", "json"]);
     cmd.assert()
-        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .stdout_with_insta("example_api_v0.3.0_json")
         .success();
 }
 
 #[test]
-fn document_private_items() {
-    // Create independent build dir so all tests can run in parallel
-    let build_dir = tempdir().unwrap();
-
-    let json = rustdoc_json_builder_for_crate("../test-apis/example_api-v0.3.0", &build_dir)
-        .document_private_items(true)
-        .build()
-        .unwrap();
-    let mut cmd = TestCmd::new().with_separate_target_dir();
-    cmd.arg("--rustdoc-json");
-    cmd.arg(json);
+fn diff_public_items_as_json() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["diff", "v0.1.0..v0.2.0", "--output-format", "json"]);
     cmd.assert()
-        .stdout_with_insta("example_api-v0.3.0_document-private-items")
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0_json")
         .success();
 }
 
 #[test]
-fn cap_lints_allow_by_default_when_diffing() {
-    let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::LintError);
-    cmd.arg("diff");
-    cmd.arg("v0.1.0..v0.1.1");
-
-    // If `missing_docs` is printed, it must mean that the lint was not capped.
-    // So require it to be absent in the output, since by default we do not want
-    // to show lint errors when diffing. Because the user typically can't do
-    // anything about it.
+fn list_public_items_include_doc_hidden() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--include",
+        "doc-hidden",
+    ]);
     cmd.assert()
-        .stderr(contains("missing_docs").not())
+        .stdout(contains(
+            "#[doc(hidden)] pub fn comprehensive_api::attributes::doc_hidden()\n",
+        ))
         .success();
 }
 
 #[test]
-fn diff_against_published_version() {
-    let mut cmd = TestCmd::new().with_test_repo();
-    cmd.arg("diff");
-    cmd.arg("0.1.0");
-    cmd.assert().stdout_with_insta("diff_published").success();
+fn list_public_items_include_doc_hidden_as_json() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--include",
+        "doc-hidden",
+        "--output-format",
+        "json",
+    ]);
+    let output = cmd.assert().success();
+    let stdout = &output.get_output().stdout;
+
+    let hidden = json_item(
+        stdout,
+        "#[doc(hidden)] pub fn comprehensive_api::attributes::doc_hidden()",
+    );
+    assert_eq!(hidden["doc_hidden"], true);
+    let visible = json_item(stdout, "pub mod comprehensive_api::attributes");
+    assert_eq!(visible["doc_hidden"], false);
 }
 
 #[test]
-fn diff_against_published_version_with_lib_name_different_from_package_name() {
-    let mut cmd = TestCmd::new();
-    cmd.arg("--manifest-path");
-    cmd.arg("../test-apis/other-lib-name/Cargo.toml");
-    cmd.arg("diff");
-    cmd.arg("0.1.0");
+fn list_public_items_omit_doc_hidden() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--include",
+        "doc-hidden",
+        "--omit",
+        "doc-hidden",
+    ]);
     cmd.assert()
-        .stdout_with_insta("other-lib-name-diff")
+        .stdout(contains("pub fn comprehensive_api::attributes::doc_hidden()").not())
+        .stdout(contains("EnumWithStrippedTupleVariants::SingleHidden(_)"))
         .success();
 }
 
-/// Tests that we can diff between two published versions of an arbitrary crate
-/// that does not need to be in the current workspace.
 #[test]
-fn diff_between_two_published_versions() {
-    let mut cmd = TestCmd::new(); // NOTE: No `.with_test_repo()` !;
-    cmd.arg("-p");
-    cmd.arg("example_api");
-    cmd.arg("diff");
-    cmd.arg("0.1.0");
-    cmd.arg("0.2.0");
+fn list_public_items_collapse_reexports() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--collapse-reexports",
+    ]);
     cmd.assert()
-        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .stdout(contains("pub struct comprehensive_api::structs::Plain // re-exported at comprehensive_api::Plain, comprehensive_api::RenamedPlain\n"))
+        .stdout(contains("pub struct comprehensive_api::RenamedPlain").not())
         .success();
 }
 
-/// Test that `cargo public-api diff latest` works
 #[test]
-fn diff_against_latest_published_version() {
-    diff_against_latest_published_version_impl(
-        Some("latest"),
-        "Resolved `diff latest` to `diff 0.3.0`",
+fn list_public_items_reexports_as_json() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--output-format",
+        "json",
+    ]);
+    let output = cmd.assert().success();
+    let alias = json_item(
+        &output.get_output().stdout,
+        "pub struct comprehensive_api::RenamedPlain",
+    );
+    assert_eq!(alias["canonical_path"], "comprehensive_api::structs::Plain");
+    assert_eq!(alias["reexport"], true);
+    assert_eq!(alias["reexport_paths"], serde_json::json!([]));
+
+    cmd.arg("--collapse-reexports");
+    let output = cmd.assert().success();
+    let canonical = json_item(
+        &output.get_output().stdout,
+        "pub struct comprehensive_api::structs::Plain",
+    );
+    assert_eq!(
+        canonical["canonical_path"],
+        "comprehensive_api::structs::Plain"
+    );
+    assert_eq!(canonical["reexport"], false);
+    assert_eq!(
+        canonical["reexport_paths"],
+        serde_json::json!([
+            "comprehensive_api::Plain",
+            "comprehensive_api::RenamedPlain"
+        ])
     );
 }
 
-/// Test that `cargo public-api diff` resolves to `cargo public-api diff latest`
+#[test]
+fn list_public_items_omit_unstable() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/nightly_api/Cargo.toml",
+        "--unstable-feature",
+        "nightly",
+    ]);
+    cmd.assert()
+        .stdout(contains(
+            "#[unstable(feature = \"unstable_fn\")] pub fn nightly_api::unstable_fn()",
+        ))
+        .stdout(contains("pub fn nightly_api::nightly_fn()"))
+        .success();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/nightly_api/Cargo.toml",
+        "--unstable-feature",
+        "nightly",
+        "--omit",
+        "unstable",
+    ]);
+    cmd.assert()
+        .stdout(contains("pub fn nightly_api::stable_fn()"))
+        .stdout(contains("unstable_fn").not())
+        .stdout(contains("nightly_api::unstable_mod").not())
+        .stdout(contains("nightly_fn").not())
+        .success();
+}
+
+#[test]
+fn list_public_items_with_path_filters() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["-sss", "--only-path", "example_api::Struct*::**"]);
+    cmd.args(["--exclude-path", "example_api::StructV2::**"]);
+    cmd.assert()
+        .stdout(
+            "#[non_exhaustive] pub struct example_api::Struct\n\
+             pub example_api::Struct::v1_field: usize\n\
+             pub example_api::Struct::v2_field: usize\n",
+        )
+        .success();
+}
+
+#[test]
+fn diff_public_items_as_markdown() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["diff", "v0.1.0..v0.2.0", "--output-format", "markdown"]);
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0_markdown")
+        .success();
+}
+
+#[test]
+fn diff_public_items_from_files_with_subcommand() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("diff");
+    cmd.arg(old);
+    cmd.arg(new);
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn diff_text_snapshot_against_file() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let snapshot = build_dir.path().join("public-api.txt");
+    std::fs::write(&snapshot, public_api_from_rustdoc_json(old).to_string()).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("diff");
+    cmd.arg(snapshot);
+    cmd.arg(new);
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn diff_structured_snapshot_against_file() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let snapshot = build_dir.path().join("public-api.json");
+    std::fs::write(
+        &snapshot,
+        serde_json::to_string(&public_api_from_rustdoc_json(old)).unwrap(),
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("diff");
+    cmd.arg(snapshot);
+    cmd.arg(new);
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn diff_structured_snapshot_with_filters() {
+    let build_dir = tempdir().unwrap();
+
+    // The snapshot is written without any filters, so it has more items than
+    // the filtered public API it is diffed against
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let snapshot = build_dir.path().join("public-api.json");
+    let unfiltered = public_api::Builder::from_rustdoc_json(&json)
+        .build()
+        .unwrap();
+    std::fs::write(&snapshot, serde_json::to_string(&unfiltered).unwrap()).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["diff", "--deny=all", "--omit", "auto-derived-impls"]);
+    cmd.args(["--only-path", "example_api::Struct"]);
+    cmd.arg(snapshot);
+    cmd.arg(json);
+    cmd.assert().success();
+}
+
+#[test]
+fn diff_text_snapshot_with_filters() {
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let snapshot = build_dir.path().join("public-api.txt");
+    std::fs::write(
+        &snapshot,
+        public_api_from_rustdoc_json(json.clone()).to_string(),
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "diff",
+        "--deny=all",
+        "--exclude-path",
+        "example_api::function",
+    ]);
+    cmd.arg(snapshot);
+    cmd.arg(json);
+    cmd.assert().success();
+}
+
+#[test]
+fn query_structured_snapshot() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let snapshot = build_dir.path().join("public-api.json");
+    let public_api = public_api_from_rustdoc_json(json);
+    std::fs::write(&snapshot, serde_json::to_string(&public_api).unwrap()).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--rustdoc-json");
+    cmd.arg(snapshot);
+    cmd.args(["query", "kind:fn"]);
+    cmd.assert()
+        .stderr(contains(
+            "A query can only be applied when building from rustdoc JSON",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_invalid_structured_snapshot() {
+    let build_dir = tempdir().unwrap();
+    let snapshot = build_dir.path().join("public-api.json");
+    std::fs::write(&snapshot, r#"{"items": 42}"#).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("diff");
+    cmd.arg(&snapshot);
+    cmd.arg(&snapshot);
+    cmd.assert()
+        .stderr(contains("Failed to parse structured snapshot"))
+        .stderr(contains("invalid type: integer `42`, expected a sequence"))
+        .failure();
+}
+
+#[test]
+fn diff_deprecated_item_as_json() {
+    let build_dir = tempdir().unwrap();
+    let old = build_dir.path().join("old.txt");
+    let new = build_dir.path().join("new.txt");
+    std::fs::write(&old, "pub mod a\npub fn a::f()\n").unwrap();
+    std::fs::write(&new, "pub mod a\n#[deprecated] pub fn a::f()\n").unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["diff", "--output-format", "json"]);
+    cmd.arg(old);
+    cmd.arg(new);
+    let output = cmd.assert().success();
+    let diff: serde_json::Value = serde_json::from_slice(&output.get_output().stdout).unwrap();
+
+    assert_eq!(diff["deprecated"][0]["old"]["deprecated"], false);
+    assert_eq!(diff["deprecated"][0]["new"]["deprecated"], true);
+}
+
+#[test]
+fn diff_text_snapshot_against_current_dir() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let snapshot = build_dir.path().join("public-api.txt");
+    std::fs::write(&snapshot, public_api_from_rustdoc_json(old).to_string()).unwrap();
+
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg(snapshot);
+    cmd.assert().stdout_with_insta("diff_published").success();
+}
+
+#[test]
+fn diff_local_crate_dirs() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "diff",
+        "../test-apis/example_api-v0.1.0",
+        "../test-apis/example_api-v0.2.0",
+    ]);
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn diff_local_crate_dir_against_current_dir() {
+    let old = std::fs::canonicalize("../test-apis/example_api-v0.1.0").unwrap();
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg(old);
+    cmd.assert().stdout_with_insta("diff_published").success();
+}
+
+#[test]
+fn stats() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "stats",
+    ]);
+    cmd.assert()
+        .stdout(contains("unsafe functions           1\n"))
+        .stdout(contains("non-exhaustive types       1\n"))
+        .stdout(contains("comprehensive_api::unions"))
+        .success();
+}
+
+#[test]
+fn stats_local_crate_dirs() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "stats",
+        "../test-apis/example_api-v0.1.0",
+        "../test-apis/example_api-v0.2.0",
+    ]);
+    cmd.assert()
+        .stdout_with_insta("example_api_stats_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn stats_local_crate_dirs_json() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--output-format",
+        "json",
+        "stats",
+        "../test-apis/example_api-v0.1.0",
+        "../test-apis/example_api-v0.2.0",
+    ]);
+    cmd.assert()
+        .stdout_with_insta("example_api_stats_v0.1.0_to_v0.2.0_json")
+        .success();
+}
+
+#[test]
+fn stats_too_many_args() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["stats", "0.1.0", "0.2.0", "0.3.0"]);
+    cmd.assert()
+        .stderr(contains("Expected at most 2 arguments, but got 3"))
+        .failure();
+}
+
+#[test]
+fn query() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "query",
+        "kind:fn",
+        "returns:impl Iterator",
+    ]);
+    cmd.assert()
+        .stdout(
+            "pub fn comprehensive_api::functions::return_iterator() -> impl core::iter::traits::iterator::Iterator<Item = u32>\n",
+        )
+        .success();
+}
+
+#[test]
+fn query_invalid_predicate() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["query", "colour:red"]);
+    cmd.assert()
+        .stderr(contains(
+            "Invalid query predicate `colour:red`: unknown key",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_crate_archive() {
+    let archive_dir = tempdir().unwrap();
+    let packaged = archive_dir.path().join("example_api-0.1.0");
+    std::fs::create_dir_all(packaged.join("src")).unwrap();
+    for file in ["Cargo.toml", "src/lib.rs"] {
+        std::fs::copy(
+            Path::new("../test-apis/example_api-v0.1.0").join(file),
+            packaged.join(file),
+        )
+        .unwrap();
+    }
+    let archive = archive_dir.path().join("example_api-0.1.0.crate");
+    let status = std::process::Command::new("tar")
+        .arg("-czf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(archive_dir.path())
+        .arg("example_api-0.1.0")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("diff");
+    cmd.arg(archive);
+    cmd.arg("../test-apis/example_api-v0.2.0");
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+#[test]
+fn diff_removed_reexport() {
+    let crates = tempdir().unwrap();
+    for (name, lib) in [
+        (
+            "old",
+            "pub mod c { pub struct B; }\npub mod a { pub use crate::c::B; }\n",
+        ),
+        ("new", "pub mod c { pub struct B; }\npub mod a {}\n"),
+    ] {
+        let dir = crates.path().join(name);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"lib\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/lib.rs"), lib).unwrap();
+    }
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["-sss", "diff"]);
+    cmd.arg(crates.path().join("old"));
+    cmd.arg(crates.path().join("new"));
+    cmd.assert()
+        .stdout(contains(
+            "-pub struct lib::a::B // re-export removed, still available at lib::c::B\n",
+        ))
+        .success();
+}
+
+#[test]
+fn document_private_items() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_builder_for_crate("../test-apis/example_api-v0.3.0", &build_dir)
+        .document_private_items(true)
+        .build()
+        .unwrap();
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--rustdoc-json");
+    cmd.arg(json);
+    cmd.assert()
+        .stdout_with_insta("example_api-v0.3.0_document-private-items")
+        .success();
+}
+
+#[test]
+fn cap_lints_allow_by_default_when_diffing() {
+    let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::LintError);
+    cmd.arg("diff");
+    cmd.arg("v0.1.0..v0.1.1");
+
+    // If `missing_docs` is printed, it must mean that the lint was not capped.
+    // So require it to be absent in the output, since by default we do not want
+    // to show lint errors when diffing. Because the user typically can't do
+    // anything about it.
+    cmd.assert()
+        .stderr(contains("missing_docs").not())
+        .success();
+}
+
+#[test]
+fn diff_against_published_version() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("diff");
+    cmd.arg("0.1.0");
+    cmd.assert().stdout_with_insta("diff_published").success();
+}
+
+/// Vendors `example_api` 0.1.0 like `cargo vendor` does, and diffs against it
+/// without network access.
+#[test]
+fn diff_against_vendored_published_version() {
+    let vendor_dir = tempdir().unwrap();
+    let vendored = vendor_dir.path().join("example_api-0.1.0");
+    std::fs::create_dir_all(vendored.join("src")).unwrap();
+    for file in ["Cargo.toml", "src/lib.rs"] {
+        std::fs::copy(
+            Path::new("../test-apis/example_api-v0.1.0").join(file),
+            vendored.join(file),
+        )
+        .unwrap();
+    }
+    std::fs::write(
+        vendored.join(".cargo-checksum.json"),
+        r#"{"files":{},"package":null}"#,
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_test_repo();
+    let cargo_config_dir = cmd.test_repo_path().join(".cargo");
+    std::fs::create_dir_all(&cargo_config_dir).unwrap();
+    std::fs::write(
+        cargo_config_dir.join("config.toml"),
+        format!(
+            "[source.crates-io]\nreplace-with = \"vendored\"\n\n[source.vendored]\ndirectory = {:?}\n",
+            vendor_dir.path()
+        ),
+    )
+    .unwrap();
+    cmd.args(["diff", "--offline", "0.1.0"]);
+    cmd.assert().stdout_with_insta("diff_published").success();
+}
+
+#[test]
+fn diff_against_published_version_with_lib_name_different_from_package_name() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--manifest-path");
+    cmd.arg("../test-apis/other-lib-name/Cargo.toml");
+    cmd.arg("diff");
+    cmd.arg("0.1.0");
+    cmd.assert()
+        .stdout_with_insta("other-lib-name-diff")
+        .success();
+}
+
+/// Tests that we can diff between two published versions of an arbitrary crate
+/// that does not need to be in the current workspace.
+#[test]
+fn diff_between_two_published_versions() {
+    let mut cmd = TestCmd::new(); // NOTE: No `.with_test_repo()` !;
+    cmd.arg("-p");
+    cmd.arg("example_api");
+    cmd.arg("diff");
+    cmd.arg("0.1.0");
+    cmd.arg("0.2.0");
+    cmd.assert()
+        .stdout_with_insta("example_api_diff_v0.1.0_to_v0.2.0")
+        .success();
+}
+
+/// Test that `cargo public-api diff latest` works
+#[test]
+fn diff_against_latest_published_version() {
+    diff_against_latest_published_version_impl(
+        Some("latest"),
+        "Resolved `diff latest` to `diff 0.3.0`",
+    );
+}
+
+/// Test that `cargo public-api diff` resolves to `cargo public-api diff latest`
 #[test]
 fn diff_implicitly_against_latest_published_version() {
     diff_against_latest_published_version_impl(None, "Resolved `diff` to `diff 0.3.0`");
@@ -992,6 +1822,71 @@ fn diff_published_with_all_features() {
         .success();
 }
 
+#[test]
+fn diff_required_bump_against_latest() {
+    let test_repo = TestRepo::new();
+    append_to_lib_rs_in_test_repo(&test_repo, "pub struct AddedSinceLatest;");
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir(test_repo.path());
+    cmd.args(["diff", "--required-bump"]);
+    cmd.assert()
+        .stdout("0.3.0 -> 0.3.1\n")
+        .stderr(contains("Resolved `diff` to `diff 0.3.0`"))
+        .success();
+}
+
+#[test]
+fn diff_required_bump_with_breaking_changes() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args([
+        "diff",
+        "0.2.0",
+        "--required-bump",
+        "--deny-insufficient-version",
+    ]);
+    cmd.assert().stdout("0.2.0 -> 0.3.0\n").success();
+}
+
+#[test]
+fn diff_required_bump_deny_insufficient_version() {
+    let test_repo = TestRepo::new();
+    append_to_lib_rs_in_test_repo(&test_repo, "pub struct AddedSinceLatest;");
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir(test_repo.path());
+    cmd.args([
+        "diff",
+        "latest",
+        "--required-bump",
+        "--deny-insufficient-version",
+    ]);
+    cmd.assert()
+        .stdout("0.3.0 -> 0.3.1\n")
+        .stderr(contains(
+            "The version in Cargo.toml (0.3.0) is lower than the required version (0.3.1)",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_required_bump_between_commits() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["diff", "v0.2.0..v0.3.0", "--required-bump"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--required-bump` can only diff the working tree against a published version",
+        ))
+        .failure();
+}
+
+#[test]
+fn deny_insufficient_version_without_required_bump() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["diff", "0.2.0", "--deny-insufficient-version"]);
+    cmd.assert().failure();
+}
+
 #[test]
 fn diff_with_features_separated_by_comma() {
     let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::Features);
@@ -1075,6 +1970,22 @@ fn short_diff_help() {
     cmd.assert().stdout_with_insta("help-short-diff").success();
 }
 
+#[test]
+fn short_stats_help() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("stats");
+    cmd.arg("-h");
+    cmd.assert().stdout_with_insta("help-short-stats").success();
+}
+
+#[test]
+fn short_query_help() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("query");
+    cmd.arg("-h");
+    cmd.assert().stdout_with_insta("help-short-query").success();
+}
+
 #[test]
 fn short_completions_help() {
     let mut cmd = TestCmd::new().with_separate_target_dir();
@@ -1110,6 +2021,22 @@ fn long_diff_help() {
     cmd.assert().stdout_with_insta("help-long-diff").success();
 }
 
+#[test]
+fn long_stats_help() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("stats");
+    cmd.arg("--help");
+    cmd.assert().stdout_with_insta("help-long-stats").success();
+}
+
+#[test]
+fn long_query_help() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("query");
+    cmd.arg("--help");
+    cmd.assert().stdout_with_insta("help-long-query").success();
+}
+
 #[test]
 fn long_help_wraps() {
     let max_allowed_line_length = 125; // 120 with some margin
@@ -1251,6 +2178,70 @@ fn features_b_c() {
     test_features(&F::new(&["feature_c"]).none()); // includes `feature_b`
 }
 
+#[test]
+fn features_matrix() {
+    let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::Features);
+    cmd.arg("--feature-matrix");
+    cmd.assert().stdout_with_insta("features-matrix").success();
+}
+
+#[test]
+fn features_matrix_can_not_diff() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--feature-matrix", "diff"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--feature-matrix` can only be used to list the public API of a package",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_feature_sets() {
+    let build_dir = tempdir().unwrap();
+    let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::Features);
+    cmd.args(["diff", "--feature-set", "", "--feature-set", "default"]);
+    cmd.args(["--feature-set", "feature_b,feature_c"]);
+    cmd.arg(features_snapshot_without_features(&build_dir));
+    cmd.assert()
+        .stdout_with_insta("diff-feature-sets")
+        .success();
+}
+
+#[test]
+fn diff_feature_powerset() {
+    let build_dir = tempdir().unwrap();
+    let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::Features);
+    cmd.args(["diff", "--feature-powerset"]);
+    cmd.arg(features_snapshot_without_features(&build_dir));
+    cmd.assert()
+        .stdout_with_insta("diff-feature-powerset")
+        .success();
+}
+
+#[test]
+fn diff_feature_powerset_with_features() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--all-features", "diff", "--feature-powerset"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--feature-powerset` and `--feature-set` can not be combined with `--features`, `--all-features` or `--no-default-features`",
+        ))
+        .failure();
+}
+
+/// A text snapshot of the public API of `test-apis/features` without any
+/// features, to diff against.
+fn features_snapshot_without_features(dir: impl AsRef<Path>) -> PathBuf {
+    let snapshot = dir.as_ref().join("public-api.txt");
+    std::fs::write(
+        &snapshot,
+        "pub mod features\n#[non_exhaustive] pub struct features::AStruct\n",
+    )
+    .unwrap();
+    snapshot
+}
+
 fn test_features(features: &F) {
     let mut cmd = TestCmd::new().with_test_repo_variant(TestRepoVariant::Features);
 
@@ -1271,12 +2262,33 @@ fn test_features(features: &F) {
         .success();
 }
 
+/// Finds the item with the given `text` in the `items` of JSON output.
+fn json_item(stdout: &[u8], text: &str) -> serde_json::Value {
+    let output: serde_json::Value = serde_json::from_slice(stdout).unwrap();
+    output["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["text"] == text)
+        .unwrap_or_else(|| panic!("no item `{text}` in JSON output"))
+        .clone()
+}
+
 fn rustdoc_json_path_for_crate(test_crate: &str, target_dir: impl AsRef<Path>) -> PathBuf {
     rustdoc_json_builder_for_crate(test_crate, target_dir)
         .build()
         .unwrap()
 }
 
+/// Simplified in the same way as [`TestCmd::new()`].
+fn public_api_from_rustdoc_json(rustdoc_json: PathBuf) -> public_api::PublicApi {
+    public_api::Builder::from_rustdoc_json(rustdoc_json)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .build()
+        .unwrap()
+}
+
 fn rustdoc_json_builder_for_crate(
     test_crate: &str,
     target_dir: impl AsRef<Path>,
@@ -0,0 +1,251 @@
+//! Parses the `#[cfg(...)]` conditions of items. Rustdoc JSON only exposes
+//! them as attribute strings, either as a HIR debug printing like
+//!
+//! ```txt
+//! #[attr = CfgTrace([NameValue { name: "feature", value: Some("std"), span: src/lib.rs:1:7: 1:22 (#0) }])]
+//! ```
+//!
+//! or, for `#[doc(cfg(...))]`, in source form like `#[doc(cfg(feature = "std"))]`.
+
+use rustdoc_types::{Attribute, Item};
+
+/// A `cfg` condition, e.g. `all(unix, feature = "std")`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Cfg {
+    /// E.g. `unix`
+    Name(String),
+
+    /// E.g. `feature = "std"`
+    NameValue(String, String),
+
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// If the item can only be built when the given cargo feature is enabled.
+    pub(crate) fn requires_feature(&self, feature: &str) -> bool {
+        match self {
+            Self::NameValue(name, value) => name == "feature" && value == feature,
+            Self::All(cfgs) => cfgs.iter().any(|cfg| cfg.requires_feature(feature)),
+            Self::Any(cfgs) => {
+                !cfgs.is_empty() && cfgs.iter().all(|cfg| cfg.requires_feature(feature))
+            }
+            Self::Name(_) | Self::Not(_) => false,
+        }
+    }
+}
+
+/// The `cfg` conditions of the item itself. Conditions of parent items are not
+/// included. Conditions that can't be parsed are skipped.
+pub(crate) fn item_cfgs(item: &Item) -> Vec<Cfg> {
+    let mut cfgs = vec![];
+    for attr in &item.attrs {
+        let Attribute::Other(attr) = attr else {
+            continue;
+        };
+
+        let parsed = if let Some(rest) = attr.strip_prefix("#[attr = CfgTrace(") {
+            Parser::new(rest).hir_cfg_list()
+        } else if let Some(rest) = attr.strip_prefix("#[doc(cfg(") {
+            Parser::new(rest).source_cfg().map(|cfg| vec![cfg])
+        } else {
+            None
+        };
+
+        for cfg in parsed.unwrap_or_default() {
+            // `#[cfg_attr(feature = "x", doc(cfg(feature = "x")))]` is common
+            if !cfgs.contains(&cfg) {
+                cfgs.push(cfg);
+            }
+        }
+    }
+    cfgs
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { rest: input }
+    }
+
+    /// Parses e.g. `[NameValue { .. }, All([..], span)]`.
+    fn hir_cfg_list(&mut self) -> Option<Vec<Cfg>> {
+        self.expect("[")?;
+        let mut cfgs = vec![];
+        while !self.eat("]") {
+            cfgs.push(self.hir_cfg()?);
+            self.eat(",");
+        }
+        Some(cfgs)
+    }
+
+    fn hir_cfg(&mut self) -> Option<Cfg> {
+        match self.word()? {
+            "NameValue" => {
+                let fields = self.group('{', '}')?;
+                let name = string_after(fields, "name: ")?;
+                Some(match string_after(fields, "value: Some(") {
+                    Some(value) => Cfg::NameValue(name, value),
+                    None => Cfg::Name(name),
+                })
+            }
+            kind @ ("All" | "Any") => {
+                self.expect("(")?;
+                let cfgs = self.hir_cfg_list()?;
+                self.skip_to_closing(')')?;
+                Some(if kind == "All" {
+                    Cfg::All(cfgs)
+                } else {
+                    Cfg::Any(cfgs)
+                })
+            }
+            "Not" => {
+                self.expect("(")?;
+                let cfg = self.hir_cfg()?;
+                self.skip_to_closing(')')?;
+                Some(Cfg::Not(Box::new(cfg)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses e.g. `all(unix, feature = "std")`.
+    fn source_cfg(&mut self) -> Option<Cfg> {
+        let word = self.word()?.to_owned();
+        if self.eat("=") {
+            return Some(Cfg::NameValue(word, self.string()?));
+        }
+        if !self.eat("(") {
+            return Some(Cfg::Name(word));
+        }
+
+        let mut cfgs = vec![];
+        while !self.eat(")") {
+            cfgs.push(self.source_cfg()?);
+            self.eat(",");
+        }
+        match word.as_str() {
+            "all" => Some(Cfg::All(cfgs)),
+            "any" => Some(Cfg::Any(cfgs)),
+            "not" if cfgs.len() == 1 => Some(Cfg::Not(Box::new(cfgs.remove(0)))),
+            _ => None,
+        }
+    }
+
+    fn word(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        (!word.is_empty()).then_some(word)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let string = string_after(self.rest, "")?;
+        self.rest = &self.rest[string.len() + 2..];
+        Some(string)
+    }
+
+    /// Returns the contents of a group delimited by `open` and `close`.
+    fn group(&mut self, open: char, close: char) -> Option<&'a str> {
+        self.skip_whitespace();
+        self.rest = self.rest.strip_prefix(open)?;
+        let contents = self.rest;
+        self.skip_to_closing(close)?;
+        Some(&contents[..contents.len() - self.rest.len() - 1])
+    }
+
+    /// Skips past the `close` that closes the current group, skipping any
+    /// nested groups such as the `(#0)` in spans.
+    fn skip_to_closing(&mut self, close: char) -> Option<()> {
+        let mut depth = 0;
+        for (index, c) in self.rest.char_indices() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth > 0 => depth -= 1,
+                c if c == close => {
+                    self.rest = &self.rest[index + 1..];
+                    return Some(());
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn expect(&mut self, token: &str) -> Option<()> {
+        self.eat(token).then_some(())
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+}
+
+/// Returns the contents of the `"`-quoted string that directly follows the
+/// first occurrence of `prefix`.
+pub(crate) fn string_after(input: &str, prefix: &str) -> Option<String> {
+    let start = input.find(prefix)? + prefix.len();
+    let quoted = input[start..].strip_prefix('"')?;
+    let end = quoted.find('"')?;
+    Some(quoted[..end].to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hir_cfg() {
+        let attr = r#"[All([NameValue { name: "feature", value: Some("nightly"), span: src/lib.rs:2:11: 2:30 (#0) }, Not(NameValue { name: "unix", value: None, span: src/lib.rs:2:36: 2:51 (#0) }, src/lib.rs:2:35: 2:52 (#0))], src/lib.rs:2:10: 2:53 (#0))])]"#;
+        assert_eq!(
+            Parser::new(attr).hir_cfg_list(),
+            Some(vec![Cfg::All(vec![
+                Cfg::NameValue("feature".to_owned(), "nightly".to_owned()),
+                Cfg::Not(Box::new(Cfg::Name("unix".to_owned()))),
+            ])])
+        );
+    }
+
+    #[test]
+    fn parse_source_cfg() {
+        assert_eq!(
+            Parser::new(r#"any(feature = "a", feature = "b")))]"#).source_cfg(),
+            Some(Cfg::Any(vec![
+                Cfg::NameValue("feature".to_owned(), "a".to_owned()),
+                Cfg::NameValue("feature".to_owned(), "b".to_owned()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn requires_feature() {
+        let nightly = Cfg::NameValue("feature".to_owned(), "nightly".to_owned());
+        let unix = Cfg::Name("unix".to_owned());
+
+        assert!(nightly.requires_feature("nightly"));
+        assert!(Cfg::All(vec![unix.clone(), nightly.clone()]).requires_feature("nightly"));
+        assert!(!Cfg::Any(vec![unix, nightly.clone()]).requires_feature("nightly"));
+        assert!(!Cfg::Not(Box::new(nightly)).requires_feature("nightly"));
+    }
+}
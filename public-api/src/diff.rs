@@ -6,8 +6,11 @@
 use crate::{
     PublicApi,
     public_item::{PublicItem, PublicItemPath},
+    semver::SemverClassification,
+    tokens::{Token, tokens_to_string},
 };
 use hashbag::HashBag;
+use rustdoc_types::Id;
 use std::collections::HashMap;
 
 type ItemsWithPath = HashMap<PublicItemPath, Vec<PublicItem>>;
@@ -15,6 +18,7 @@ type ItemsWithPath = HashMap<PublicItemPath, Vec<PublicItem>>;
 /// An item has changed in the public API. Two [`PublicItem`]s are considered
 /// the same if their `path` is the same.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChangedPublicItem {
     /// How the item used to look.
     pub old: PublicItem,
@@ -32,6 +36,60 @@ impl ChangedPublicItem {
             ordering => ordering,
         }
     }
+
+    /// Diffs the tokens of the old and the new item, so that you can highlight
+    /// exactly what changed, e.g. a single generic bound. The runs are in
+    /// rendering order, so the old item is rendered by the [`TokenRun::Common`]
+    /// and [`TokenRun::Removed`] runs, and the new item by the
+    /// [`TokenRun::Common`] and [`TokenRun::Inserted`] runs.
+    #[must_use]
+    pub fn token_diff(&self) -> Vec<TokenRun<'_>> {
+        let old_tokens: Vec<&Token> = self.old.tokens().collect();
+        let new_tokens: Vec<&Token> = self.new.tokens().collect();
+
+        let mut runs: Vec<TokenRun<'_>> = vec![];
+        for result in diff::slice(&old_tokens, &new_tokens) {
+            let run = match result {
+                diff::Result::Both(&token, _) => TokenRun::Common(vec![token]),
+                diff::Result::Left(&token) => TokenRun::Removed(vec![token]),
+                diff::Result::Right(&token) => TokenRun::Inserted(vec![token]),
+            };
+            // Extend the last run if it is of the same kind
+            match (runs.last_mut(), run) {
+                (Some(TokenRun::Common(tokens)), TokenRun::Common(more))
+                | (Some(TokenRun::Removed(tokens)), TokenRun::Removed(more))
+                | (Some(TokenRun::Inserted(tokens)), TokenRun::Inserted(more)) => {
+                    tokens.extend(more);
+                }
+                (_, run) => runs.push(run),
+            }
+        }
+        runs
+    }
+}
+
+/// A run of consecutive tokens in the [`ChangedPublicItem::token_diff`] of a
+/// changed item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenRun<'a> {
+    /// Tokens in both the old and the new item.
+    Common(Vec<&'a Token>),
+
+    /// Tokens only in the old item.
+    Removed(Vec<&'a Token>),
+
+    /// Tokens only in the new item.
+    Inserted(Vec<&'a Token>),
+}
+
+impl<'a> TokenRun<'a> {
+    /// The tokens of the run, regardless of kind of run.
+    #[must_use]
+    pub fn tokens(&self) -> &[&'a Token] {
+        match self {
+            Self::Common(tokens) | Self::Removed(tokens) | Self::Inserted(tokens) => tokens,
+        }
+    }
 }
 
 /// The return value of [`Self::between`]. To quickly get a sense of what it
@@ -39,7 +97,9 @@ impl ChangedPublicItem {
 /// ```txt
 /// println!("{:#?}", public_api_diff);
 /// ```
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive] // More kinds of changes might be added in the future
 pub struct PublicApiDiff {
     /// Items that have been removed from the public API. A MAJOR change, in
     /// semver terminology. Sorted.
@@ -48,9 +108,23 @@ pub struct PublicApiDiff {
     /// Items in the public API that has been changed. Generally a MAJOR change,
     /// but exceptions exist. For example, if the return value of a method is
     /// changed from `ExplicitType` to `Self` and `Self` is the same as
-    /// `ExplicitType`.
+    /// `ExplicitType`. Use [`Self::classify`] to tell them apart.
     pub changed: Vec<ChangedPublicItem>,
 
+    /// Items that have been marked `#[deprecated]` without any other change. A
+    /// MINOR change, in semver terminology. Such items are not part of
+    /// [`Self::changed`]. Sorted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deprecated: Vec<ChangedPublicItem>,
+
+    /// Items that have likely been moved to another path or renamed, e.g.
+    /// `a::Foo` that became `a::inner::Foo`. Found heuristically, see
+    /// [`Self::between`]. The old path is gone, so this is a MAJOR change, in
+    /// semver terminology. Such items are not part of [`Self::removed`] and
+    /// [`Self::added`]. Sorted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub moved: Vec<ChangedPublicItem>,
+
     /// Items that have been added to public API. A MINOR change, in semver
     /// terminology. Sorted.
     pub added: Vec<PublicItem>,
@@ -61,6 +135,15 @@ impl PublicApiDiff {
     /// library, e.g. different releases. The input parameters `old` and `new`
     /// is the output of two different invocations of
     /// [`crate::Builder::build`].
+    ///
+    /// A removed item is paired with an added item as [moved](Self::moved) if
+    /// the items have the same name and render the same apart from the path,
+    /// and no other item matches either of them. The children of paired items,
+    /// e.g. fields and methods, are paired too if they render the same apart
+    /// from the path of their parent. Items with the same parent that render
+    /// the same apart from the name are paired as renamed if, in addition, at
+    /// least one of their children is paired that way, since e.g. two
+    /// functions with the same signature are often unrelated.
     #[must_use]
     pub fn between(old: PublicApi, new: PublicApi) -> Self {
         // We must use a HashBag, because with a HashSet we would lose public
@@ -85,6 +168,20 @@ impl PublicApiDiff {
         let mut changed: Vec<ChangedPublicItem> = vec![];
         let mut added: Vec<PublicItem> = vec![];
 
+        // Re-export aliases are not part of the tokens, so an item whose
+        // aliases changed is in both APIs. Report it as changed anyway, since
+        // removing a re-export breaks downstream code
+        for (old_item, _) in old.set_iter() {
+            if let Some((new_item, _)) = new.get(old_item)
+                && new_item.reexport_paths() != old_item.reexport_paths()
+            {
+                changed.push(ChangedPublicItem {
+                    old: old_item.clone(),
+                    new: new_item.clone(),
+                });
+            }
+        }
+
         // Figure out all paths of items that are either removed or added. Later
         // we will match paths that have been both removed and added (i.e.
         // changed)
@@ -108,14 +205,27 @@ impl PublicApiDiff {
             }
         }
 
+        // Items removed from one path and added to another are likely moved or
+        // renamed rather than unrelated
+        let (mut moved, mut removed, mut added) = find_moved(removed, added);
+
+        // Report items that only became deprecated separately, so that
+        // deprecations can be announced without wading through other changes
+        let (mut deprecated, mut changed): (Vec<_>, Vec<_>) =
+            changed.into_iter().partition(is_newly_deprecated);
+
         // Make output predictable and stable
         removed.sort_by(PublicItem::grouping_cmp);
         changed.sort_by(ChangedPublicItem::grouping_cmp);
+        deprecated.sort_by(ChangedPublicItem::grouping_cmp);
+        moved.sort_by(ChangedPublicItem::grouping_cmp);
         added.sort_by(PublicItem::grouping_cmp);
 
         Self {
             removed,
             changed,
+            deprecated,
+            moved,
             added,
         }
     }
@@ -123,8 +233,242 @@ impl PublicApiDiff {
     /// Check whether the diff is empty
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.removed.is_empty() && self.changed.is_empty() && self.added.is_empty()
+        self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.deprecated.is_empty()
+            && self.moved.is_empty()
+            && self.added.is_empty()
+    }
+
+    /// If `removed` is a removed re-export alias, e.g. `a::B` after removing
+    /// `pub use c::B;` from module `a`, and the item at its [canonical
+    /// path](PublicItem::canonical_path) was neither removed nor moved, returns
+    /// that path, e.g. `c::B`. The item is then still available, just not via
+    /// the removed re-export.
+    #[must_use]
+    pub fn still_available_at<'a>(&self, removed: &'a PublicItem) -> Option<&'a str> {
+        let canonical_path = removed.canonical_path();
+        let is_gone =
+            |item: &PublicItem| !item.is_reexport() && item.canonical_path() == canonical_path;
+        (removed.is_reexport()
+            && !canonical_path.is_empty()
+            && !self.removed.iter().any(is_gone)
+            && !self.moved.iter().any(|moved| is_gone(&moved.old)))
+        .then_some(canonical_path)
+    }
+
+    /// Classifies each removed, changed, deprecated, moved and added item according to semver.
+    /// Use [`SemverClassification::required_bump`] to get the version bump
+    /// required by the diff as a whole. See [`crate::semver`] for more info.
+    #[must_use]
+    pub fn classify(&self) -> SemverClassification<'_> {
+        crate::semver::classify(self)
+    }
+}
+
+/// If the only difference between the old and the new item is that the new item
+/// is `#[deprecated]`.
+fn is_newly_deprecated(changed: &ChangedPublicItem) -> bool {
+    !changed.old.is_deprecated()
+        && changed.new.is_deprecated()
+        && changed.old.tokens == crate::semver::without_deprecation(&changed.new.tokens)
+}
+
+/// Splits `removed` and `added` into items that were likely moved or renamed,
+/// and items that were truly removed and added. See [`PublicApiDiff::between`]
+/// for the heuristic.
+fn find_moved(
+    removed: Vec<PublicItem>,
+    added: Vec<PublicItem>,
+) -> (Vec<ChangedPublicItem>, Vec<PublicItem>, Vec<PublicItem>) {
+    let mut removed_paired = vec![false; removed.len()];
+    let mut added_paired = vec![false; added.len()];
+
+    let removed_children = children_by_parent(&removed);
+    let added_children = children_by_parent(&added);
+    let children = |r: usize, a: usize| {
+        (
+            removed_children
+                .get(&removed[r].id)
+                .map_or(&[][..], Vec::as_slice),
+            added_children
+                .get(&added[a].id)
+                .map_or(&[][..], Vec::as_slice),
+        )
+    };
+
+    // Pairs of indices, with the old and new path of the item they were found
+    // from, so that children can be matched with the path replaced
+    let mut pairs: Vec<(usize, usize, Vec<String>, String)> = vec![];
+    for (key, needs_matching_child) in [(move_key as fn(&_) -> _, false), (rename_key, true)] {
+        let added_keys = unique_keys(&added, &added_paired, key);
+        for (key, r) in unique_keys(&removed, &removed_paired, key) {
+            let Some(&a) = added_keys.get(&key) else {
+                continue;
+            };
+            if removed_paired[r] || added_paired[a] {
+                continue;
+            }
+            let (Some(old), Some(new)) = (removed[r].rendered_path(), added[a].rendered_path())
+            else {
+                continue;
+            };
+            let old_path: Vec<String> = old.names.iter().map(|name| (*name).to_owned()).collect();
+            let new_path = new.names.join("::");
+            if needs_matching_child {
+                let (old_children, new_children) = children(r, a);
+                let has_matching_child = old_children.iter().any(|&child| {
+                    let expected = with_path_replaced(&removed[child], &old_path, &new_path);
+                    new_children
+                        .iter()
+                        .any(|&c| added[c].to_string() == expected)
+                });
+                if !has_matching_child {
+                    continue;
+                }
+            }
+            removed_paired[r] = true;
+            added_paired[a] = true;
+            pairs.push((r, a, old_path, new_path));
+        }
+    }
+
+    // Then pair the children of paired items, e.g. the `impl`s and methods of
+    // a moved struct
+    let mut unvisited: Vec<usize> = (0..pairs.len()).collect();
+    while let Some(index) = unvisited.pop() {
+        let (r, a, old_path, new_path) = pairs[index].clone();
+        let (old_children, new_children) = children(r, a);
+        for &child in old_children {
+            if removed_paired[child] {
+                continue;
+            }
+            let expected = with_path_replaced(&removed[child], &old_path, &new_path);
+            if let Some(&new_child) = new_children
+                .iter()
+                .find(|&&c| !added_paired[c] && added[c].to_string() == expected)
+            {
+                removed_paired[child] = true;
+                added_paired[new_child] = true;
+                unvisited.push(pairs.len());
+                pairs.push((child, new_child, old_path.clone(), new_path.clone()));
+            }
+        }
+    }
+
+    let mut removed: Vec<Option<PublicItem>> = removed.into_iter().map(Some).collect();
+    let mut added: Vec<Option<PublicItem>> = added.into_iter().map(Some).collect();
+    let moved = pairs
+        .into_iter()
+        .filter_map(|(r, a, _, _)| {
+            Some(ChangedPublicItem {
+                old: removed[r].take()?,
+                new: added[a].take()?,
+            })
+        })
+        .collect();
+    (
+        moved,
+        removed.into_iter().flatten().collect(),
+        added.into_iter().flatten().collect(),
+    )
+}
+
+/// Renders `item` with each occurrence of the path `old` replaced by `new`.
+/// Whole path segments are compared, so that replacing `a::B` leaves e.g.
+/// `a::Bc` and `x::a::B` alone.
+fn with_path_replaced(item: &PublicItem, old: &[String], new: &str) -> String {
+    let tokens: Vec<&Token> = item.tokens().collect();
+    let path_len = (old.len() * 2).saturating_sub(1);
+    let is_path_at = |index: usize| {
+        path_len > 0
+            && tokens.len() >= index + path_len
+            && (index == 0 || tokens[index - 1].text() != "::")
+            && (tokens[index..index + path_len].iter().enumerate()).all(|(i, token)| {
+                if i % 2 == 0 {
+                    !matches!(token, Token::Symbol(_)) && token.text() == old[i / 2]
+                } else {
+                    token.text() == "::"
+                }
+            })
+    };
+
+    let mut rendered = String::new();
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        if is_path_at(index) {
+            rendered.push_str(new);
+            index += path_len;
+        } else {
+            rendered.push_str(token.text());
+            index += 1;
+        }
+    }
+    rendered
+}
+
+/// Items with the same key are moved, if the key is unique: the item renders
+/// the same except for the path, and has the same name.
+fn move_key(item: &PublicItem) -> Option<String> {
+    let path = item.rendered_path()?;
+    Some(format!(
+        "{}\n{}\n{}",
+        tokens_to_string(path.before),
+        path.names.last()?,
+        tokens_to_string(path.after)
+    ))
+}
+
+/// Items with the same key are renamed, if the key is unique and a child
+/// matches too: the item renders the same except for the name, and has the
+/// same parent.
+fn rename_key(item: &PublicItem) -> Option<String> {
+    let path = item.rendered_path()?;
+    let (_, parent) = path.names.split_last()?;
+    if parent.is_empty() {
+        return None;
     }
+    Some(format!(
+        "{}\n{}\n{}",
+        tokens_to_string(path.before),
+        parent.join("::"),
+        tokens_to_string(path.after)
+    ))
+}
+
+/// Maps each key to the index of the only item with that key. Keys of several
+/// items are left out, and so are items that are already paired.
+fn unique_keys(
+    items: &[PublicItem],
+    paired: &[bool],
+    key: fn(&PublicItem) -> Option<String>,
+) -> HashMap<String, usize> {
+    let mut indices: HashMap<String, Option<usize>> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        if paired[index] {
+            continue;
+        }
+        if let Some(key) = key(item) {
+            indices
+                .entry(key)
+                .and_modify(|i| *i = None)
+                .or_insert(Some(index));
+        }
+    }
+    indices
+        .into_iter()
+        .filter_map(|(key, index)| Some((key, index?)))
+        .collect()
+}
+
+fn children_by_parent(items: &[PublicItem]) -> HashMap<Id, Vec<usize>> {
+    let mut children: HashMap<Id, Vec<usize>> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        if let Some(parent_id) = item.parent_id {
+            children.entry(parent_id).or_default().push(index);
+        }
+    }
+    children
 }
 
 /// Converts a set (read: bag) of public items into a hash map that maps a given
@@ -159,6 +503,8 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![item_with_path("foo")],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![],
         };
         assert_eq!(actual, expected);
@@ -174,6 +520,8 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![item_with_path("foo")],
         };
         assert_eq!(actual, expected);
@@ -193,6 +541,8 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![item_with_path("2")],
         };
         assert_eq!(actual, expected);
@@ -212,6 +562,8 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![item_with_path("2")],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![],
         };
         assert_eq!(actual, expected);
@@ -257,6 +609,8 @@ mod tests {
                     new: fn_with_param_type(&["a", "b"], "i64"),
                 },
             ],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![item_with_path("4"), item_with_path("4")],
         };
         assert_eq!(actual, expected);
@@ -284,6 +638,8 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![fn_with_param_type(&["a", "b"], "u8")],
         };
         let actual = PublicApiDiff::between(old, new);
@@ -300,12 +656,211 @@ mod tests {
         let expected = PublicApiDiff {
             removed: vec![],
             changed: vec![],
+            deprecated: vec![],
+            moved: vec![],
             added: vec![],
         };
         assert_eq!(actual, expected);
         assert!(actual.is_empty());
     }
 
+    #[test]
+    fn newly_deprecated_item_is_not_changed() {
+        let deprecated = |mut item: PublicItem| {
+            item.tokens
+                .splice(0..0, [Token::Annotation("#[deprecated]".to_string()), w()]);
+            item
+        };
+        let old = api([
+            fn_with_param_type(&["a", "b"], "i32"),
+            fn_with_param_type(&["a", "c"], "i32"),
+        ]);
+        let new = api([
+            deprecated(fn_with_param_type(&["a", "b"], "i32")),
+            deprecated(fn_with_param_type(&["a", "c"], "i64")),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        let expected = PublicApiDiff {
+            removed: vec![],
+            changed: vec![ChangedPublicItem {
+                old: fn_with_param_type(&["a", "c"], "i32"),
+                new: deprecated(fn_with_param_type(&["a", "c"], "i64")),
+            }],
+            deprecated: vec![ChangedPublicItem {
+                old: fn_with_param_type(&["a", "b"], "i32"),
+                new: deprecated(fn_with_param_type(&["a", "b"], "i32")),
+            }],
+            moved: vec![],
+            added: vec![],
+        };
+        assert_eq!(actual, expected);
+        assert!(!actual.is_empty());
+    }
+
+    #[test]
+    fn moved_items() {
+        let old = api([
+            fn_with_param_type(&["a", "moved"], "i32"),
+            fn_with_param_type(&["a", "removed"], "i64"),
+        ]);
+        let new = api([
+            fn_with_param_type(&["a", "b", "moved"], "i32"),
+            fn_with_param_type(&["a", "added"], "u64"),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        let expected = PublicApiDiff {
+            removed: vec![fn_with_param_type(&["a", "removed"], "i64")],
+            changed: vec![],
+            deprecated: vec![],
+            moved: vec![ChangedPublicItem {
+                old: fn_with_param_type(&["a", "moved"], "i32"),
+                new: fn_with_param_type(&["a", "b", "moved"], "i32"),
+            }],
+            added: vec![fn_with_param_type(&["a", "added"], "u64")],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn renamed_items_need_a_matching_child() {
+        let old = api([
+            with_ids(item_with_tokens(&["a", "Old"], "struct"), 1, None),
+            with_ids(fn_with_param_type(&["a", "Old", "f"], "i32"), 2, Some(1)),
+            fn_with_param_type(&["a", "old_fn"], "u8"),
+        ]);
+        let new = api([
+            with_ids(item_with_tokens(&["a", "New"], "struct"), 7, None),
+            with_ids(fn_with_param_type(&["a", "New", "f"], "i32"), 8, Some(7)),
+            fn_with_param_type(&["a", "new_fn"], "u8"),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        let moved: Vec<String> = actual.moved.iter().map(|m| m.new.to_string()).collect();
+        assert_eq!(moved, vec!["struct a::New", "pub fn a::New::f(x: i32)"]);
+        assert_eq!(
+            actual.removed,
+            vec![fn_with_param_type(&["a", "old_fn"], "u8")]
+        );
+        assert_eq!(
+            actual.added,
+            vec![fn_with_param_type(&["a", "new_fn"], "u8")]
+        );
+    }
+
+    #[test]
+    fn ambiguous_moves_are_removed_and_added() {
+        let old = api([
+            fn_with_param_type(&["a", "f"], "i32"),
+            fn_with_param_type(&["b", "f"], "i32"),
+        ]);
+        let new = api([fn_with_param_type(&["c", "f"], "i32")]);
+
+        let actual = PublicApiDiff::between(old, new);
+        assert!(actual.moved.is_empty());
+        assert_eq!(actual.removed.len(), 2);
+        assert_eq!(actual.added.len(), 1);
+    }
+
+    #[test]
+    fn children_of_moved_items_are_moved() {
+        let old = api([
+            with_ids(item_with_tokens(&["a", "S"], "struct"), 1, None),
+            with_ids(fn_with_param_type(&["a", "S", "f"], "i32"), 2, Some(1)),
+            // Makes the move of `f` ambiguous on its own
+            with_ids(fn_with_param_type(&["c", "f"], "i32"), 3, None),
+        ]);
+        let new = api([
+            with_ids(item_with_tokens(&["a", "b", "S"], "struct"), 7, None),
+            with_ids(fn_with_param_type(&["a", "b", "S", "f"], "i32"), 8, Some(7)),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        assert_eq!(actual.removed, vec![fn_with_param_type(&["c", "f"], "i32")]);
+        assert!(actual.added.is_empty());
+        let moved: Vec<String> = actual.moved.iter().map(|m| m.new.to_string()).collect();
+        assert_eq!(moved, vec!["struct a::b::S", "pub fn a::b::S::f(x: i32)"]);
+    }
+
+    #[test]
+    fn only_whole_paths_of_moved_items_are_replaced() {
+        let old = api([
+            with_ids(item_with_tokens(&["a", "S"], "struct"), 1, None),
+            with_ids(fn_with_param_type(&["a", "S", "f"], "a::Sx"), 2, Some(1)),
+            with_ids(fn_with_param_type(&["a", "S", "g"], "a"), 3, Some(1)),
+        ]);
+        let new = api([
+            with_ids(item_with_tokens(&["a", "b", "S"], "struct"), 7, None),
+            with_ids(
+                fn_with_param_type(&["a", "b", "S", "f"], "a::Sx"),
+                8,
+                Some(7),
+            ),
+            with_ids(fn_with_param_type(&["a", "b", "S", "g"], "a"), 9, Some(7)),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        assert!(actual.removed.is_empty());
+        assert!(actual.added.is_empty());
+        assert_eq!(actual.moved.len(), 3);
+    }
+
+    #[test]
+    fn path_replacement_respects_segments() {
+        let old_path = ["a".to_owned(), "S".to_owned()];
+        let mut item = item_with_tokens(&["x", "a", "S"], "struct");
+        item.tokens
+            .extend([w(), t("a"), s("::"), t("Sx"), w(), t("a"), s("::"), t("S")]);
+        assert_eq!(
+            with_path_replaced(&item, &old_path, "a::b::S"),
+            "struct x::a::S a::Sx a::b::S"
+        );
+    }
+
+    #[test]
+    fn token_diff_of_changed_param_type() {
+        let changed = ChangedPublicItem {
+            old: fn_with_param_type(&["a", "b"], "i32"),
+            new: fn_with_param_type(&["a", "b"], "i64"),
+        };
+
+        let token_diff = changed.token_diff();
+        let [
+            TokenRun::Common(before),
+            TokenRun::Removed(removed),
+            TokenRun::Inserted(inserted),
+            TokenRun::Common(after),
+        ] = token_diff.as_slice()
+        else {
+            panic!("unexpected token diff: {token_diff:?}");
+        };
+        assert_eq!(before.last(), Some(&&w()));
+        assert_eq!(removed, &[&t("i32")]);
+        assert_eq!(inserted, &[&t("i64")]);
+        assert_eq!(after, &[&q(")")]);
+    }
+
+    #[test]
+    fn token_diff_of_identical_items_is_common() {
+        let item = fn_with_param_type(&["a", "b"], "i32");
+        let changed = ChangedPublicItem {
+            old: item.clone(),
+            new: item.clone(),
+        };
+
+        assert_eq!(
+            changed.token_diff(),
+            vec![TokenRun::Common(item.tokens().collect())]
+        );
+    }
+
+    fn with_ids(mut item: PublicItem, id: u32, parent_id: Option<u32>) -> PublicItem {
+        item.id = Id(id);
+        item.parent_id = parent_id.map(Id);
+        item
+    }
+
     fn item_with_path(path_str: &str) -> PublicItem {
         new_public_item(
             path_str
@@ -316,6 +871,20 @@ mod tests {
         )
     }
 
+    /// E.g. `struct a::S` for `kind` `struct`
+    fn item_with_tokens(path_str: &[&str], kind: &str) -> PublicItem {
+        let path: Vec<_> = path_str
+            .iter()
+            .map(std::string::ToString::to_string)
+            .collect();
+        let mut tokens = vec![k(kind), w()];
+        tokens.extend(itertools::intersperse(
+            path.iter().cloned().map(Token::identifier),
+            Token::symbol("::"),
+        ));
+        new_public_item(path, tokens)
+    }
+
     fn api(items: impl IntoIterator<Item = PublicItem>) -> PublicApi {
         PublicApi {
             items: items.into_iter().collect(),
@@ -351,6 +920,14 @@ mod tests {
             tokens,
             parent_id: None,
             id: DUMMY_ID,
+            reason_if_added: crate::semver::SemverReason::ItemAdded,
+            doc_hidden: false,
+            unstable: false,
+            kind: None,
+            path: vec![],
+            canonical_path: String::new(),
+            reexport: false,
+            reexport_paths: vec![],
         }
     }
 
@@ -14,6 +14,17 @@ pub enum Error {
     /// permissions on the rustdoc JSON input file.
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+
+    /// Occurs if a [`crate::query::Query`] predicate can't be parsed, e.g.
+    /// because its key is unknown.
+    #[error("Invalid query predicate {0}")]
+    InvalidQuery(String),
+
+    /// Occurs if a [`crate::query::Query`] is applied to a public API that is
+    /// not built from rustdoc JSON, e.g. with
+    /// [`crate::Builder::apply_filters`].
+    #[error("A query can only be applied when building from rustdoc JSON")]
+    QueryNeedsRustdocJson,
 }
 
 /// Shorthand for [`std::result::Result<T, public_api::Error>`].
@@ -1,5 +1,6 @@
 use rustdoc_types::{Id, Item};
 
+use crate::item_kind::ItemKind;
 use crate::nameable_item::NameableItem;
 use crate::path_component::PathComponent;
 use crate::public_item::PublicItemPath;
@@ -50,6 +51,26 @@ impl<'c> IntermediatePublicItem<'c> {
         self.id
     }
 
+    /// See [`ItemKind`]. Functions in traits and `impl`s are methods.
+    #[must_use]
+    pub fn kind(&self) -> ItemKind {
+        let path = self.path();
+        let parent = path.len().checked_sub(2).map(|index| path[index].item.item);
+        ItemKind::of(self.item(), parent)
+    }
+
+    /// The names in the path of the item, e.g. `["a", "B", "f"]`. `impl`s do
+    /// not have names, so items of an `impl` have the path of the type or
+    /// trait the `impl` belongs to, and so does the `impl` itself.
+    #[must_use]
+    pub fn path_names(&self) -> Vec<String> {
+        self.path()
+            .iter()
+            .filter_map(|p| p.item.name())
+            .map(String::from)
+            .collect()
+    }
+
     /// See [`crate::item_processor::sorting_prefix()`] docs for an explanation why we have this.
     #[must_use]
     pub fn sortable_path(&self, context: &RenderingContext) -> PublicItemPath {
@@ -59,6 +80,31 @@ impl<'c> IntermediatePublicItem<'c> {
             .collect()
     }
 
+    /// If the item or any item in its path has `#[doc(hidden)]`, e.g. a
+    /// function in a hidden module.
+    #[must_use]
+    pub fn is_doc_hidden(&self) -> bool {
+        self.path()
+            .iter()
+            .any(|p| crate::item_processor::is_doc_hidden(p.item.item))
+    }
+
+    /// If the item or any item in its path is `#[unstable]`, or can only be
+    /// built with any of the given `unstable_features` enabled. See
+    /// [`crate::Builder::unstable_features`].
+    #[must_use]
+    pub fn is_unstable(&self, unstable_features: &[String]) -> bool {
+        self.path().iter().any(|p| {
+            let item = p.item.item;
+            crate::item_processor::unstable_feature(item).is_some()
+                || crate::cfg::item_cfgs(item).iter().any(|cfg| {
+                    unstable_features
+                        .iter()
+                        .any(|feature| cfg.requires_feature(feature))
+                })
+        })
+    }
+
     #[must_use]
     pub fn path_contains_renamed_item(&self) -> bool {
         self.path().iter().any(|m| m.item.overridden_name.is_some())
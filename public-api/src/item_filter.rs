@@ -0,0 +1,104 @@
+//! Applies the filters of a [`crate::Builder`] to a [`PublicApi`] that is
+//! already built, e.g. one read from a snapshot. See
+//! [`crate::Builder::apply_filters`].
+
+use std::collections::HashSet;
+
+use rustdoc_types::Id;
+
+use crate::{BuilderOptions, ImplKind, ItemKind, PublicApi, PublicItem, path_filter::PathFilter};
+
+pub(crate) fn apply(
+    public_api: PublicApi,
+    options: BuilderOptions,
+    path_filter: &PathFilter,
+) -> PublicApi {
+    let mut items = public_api.items;
+
+    // Items of an omitted `impl` are omitted too, like when building
+    let omitted_impls: HashSet<Id> = items
+        .iter()
+        .filter(|item| is_omitted_impl(item, options))
+        .map(PublicItem::id)
+        .collect();
+    items.retain(|item| {
+        !is_omitted_impl(item, options)
+            && !item
+                .parent_id()
+                .is_some_and(|parent| omitted_impls.contains(&parent))
+    });
+
+    items.retain(|item| !(options.omit_doc_hidden && item.is_doc_hidden()));
+    items.retain(|item| !(options.omit_unstable && item.is_unstable()));
+
+    // Items without a known path, like `impl`s read from a text snapshot,
+    // can't be matched, so they are kept
+    items.retain(|item| {
+        item.path().is_empty()
+            || path_filter.keeps_path(&item.path().iter().map(String::as_str).collect::<Vec<_>>())
+    });
+
+    if options.collapse_reexports {
+        collapse_reexports(&mut items);
+    }
+
+    PublicApi {
+        items,
+        missing_item_ids: public_api.missing_item_ids,
+    }
+}
+
+fn is_omitted_impl(item: &PublicItem, options: BuilderOptions) -> bool {
+    match item.kind() {
+        Some(ItemKind::Impl(ImplKind::Blanket)) => options.omit_blanket_impls,
+        Some(ItemKind::Impl(ImplKind::AutoTrait)) => options.omit_auto_trait_impls,
+        Some(ItemKind::Impl(ImplKind::AutoDerived)) => options.omit_auto_derived_impls,
+        _ => false,
+    }
+}
+
+/// Omits re-export aliases and adds their paths to the
+/// [`PublicItem::reexport_paths`] of the item at the canonical path. Only the
+/// aliases of the item itself are added, not e.g. the fields of an aliased
+/// struct, which are the items whose parent path is an alias too.
+fn collapse_reexports(items: &mut Vec<PublicItem>) {
+    let alias_paths: HashSet<&[String]> = items
+        .iter()
+        .filter(|item| item.is_reexport())
+        .map(PublicItem::path)
+        .collect();
+    let aliases: Vec<(Option<ItemKind>, String, String)> = items
+        .iter()
+        .filter(|item| item.is_reexport() && !is_impl(item))
+        .filter(|item| {
+            item.path()
+                .split_last()
+                .is_none_or(|(_, parent_path)| !alias_paths.contains(parent_path))
+        })
+        .map(|item| {
+            (
+                item.kind(),
+                item.canonical_path().to_owned(),
+                item.path().join("::"),
+            )
+        })
+        .collect();
+
+    items.retain(|item| !item.is_reexport());
+    for item in items.iter_mut().filter(|item| !is_impl(item)) {
+        let paths = aliases
+            .iter()
+            .filter(|(kind, canonical_path, _)| {
+                *kind == item.kind() && canonical_path == item.canonical_path()
+            })
+            .map(|(_, _, path)| path.clone())
+            .collect::<Vec<_>>();
+        item.reexport_paths.extend(paths);
+        item.reexport_paths.sort();
+        item.reexport_paths.dedup();
+    }
+}
+
+fn is_impl(item: &PublicItem) -> bool {
+    matches!(item.kind(), Some(ItemKind::Impl(_)))
+}
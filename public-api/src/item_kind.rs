@@ -0,0 +1,172 @@
+use rustdoc_types::{Item, ItemEnum};
+
+use crate::item_processor::ImplKind;
+
+/// What kind of item a [`crate::PublicItem`] is. Displayed in `snake_case`,
+/// e.g. `struct_field` or `trait_impl`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum ItemKind {
+    /// E.g. `pub mod a`
+    Module,
+
+    /// E.g. `pub extern crate a`
+    ExternCrate,
+
+    /// A `pub use` that can't be inlined, e.g. of a primitive type
+    Use,
+
+    /// A primitive type such as `u8`, in the standard library
+    Primitive,
+
+    /// A `macro_rules!` macro
+    Macro,
+
+    /// A function-like, derive or attribute procedural macro
+    ProcMacro,
+
+    /// E.g. `pub enum A`
+    Enum,
+
+    /// E.g. `pub union A`
+    Union,
+
+    /// E.g. `pub struct A`
+    Struct,
+
+    /// A field of a struct, union or struct-like enum variant
+    StructField,
+
+    /// A variant of an enum
+    Variant,
+
+    /// E.g. `pub const A: u8`
+    Constant,
+
+    /// E.g. `pub static A: u8`
+    Static,
+
+    /// E.g. `pub trait A`
+    Trait,
+
+    /// An associated type of a trait or an `impl`
+    AssocType,
+
+    /// An associated const of a trait or an `impl`
+    AssocConst,
+
+    /// A free function, e.g. `pub fn a()`
+    Function,
+
+    /// A function of a trait or an `impl`, with or without `self`
+    Method,
+
+    /// E.g. `pub type A = u8`
+    TypeAlias,
+
+    /// An `impl` block
+    Impl(ImplKind),
+
+    /// A type in an `extern` block
+    ExternType,
+
+    /// E.g. `pub trait A = B`
+    TraitAlias,
+}
+
+impl ItemKind {
+    /// All kinds, e.g. to tell if a kind given by a user exists.
+    pub(crate) const ALL: &[Self] = &[
+        Self::Module,
+        Self::ExternCrate,
+        Self::Use,
+        Self::Primitive,
+        Self::Macro,
+        Self::ProcMacro,
+        Self::Enum,
+        Self::Union,
+        Self::Struct,
+        Self::StructField,
+        Self::Variant,
+        Self::Constant,
+        Self::Static,
+        Self::Trait,
+        Self::AssocType,
+        Self::AssocConst,
+        Self::Function,
+        Self::Method,
+        Self::TypeAlias,
+        Self::Impl(ImplKind::Inherent),
+        Self::Impl(ImplKind::Trait),
+        Self::Impl(ImplKind::AutoDerived),
+        Self::Impl(ImplKind::AutoTrait),
+        Self::Impl(ImplKind::Blanket),
+        Self::ExternType,
+        Self::TraitAlias,
+    ];
+
+    /// The kind of `item`, which is in `parent`, if any.
+    pub(crate) fn of(item: &Item, parent: Option<&Item>) -> Self {
+        let in_trait_or_impl = parent
+            .is_some_and(|parent| matches!(parent.inner, ItemEnum::Trait(_) | ItemEnum::Impl(_)));
+        match &item.inner {
+            ItemEnum::Module(_) => Self::Module,
+            ItemEnum::ExternCrate { .. } => Self::ExternCrate,
+            ItemEnum::Use(_) => Self::Use,
+            ItemEnum::Primitive(_) => Self::Primitive,
+            ItemEnum::Macro(_) => Self::Macro,
+            ItemEnum::ProcMacro(_) => Self::ProcMacro,
+            ItemEnum::Enum(_) => Self::Enum,
+            ItemEnum::Union(_) => Self::Union,
+            ItemEnum::Struct(_) => Self::Struct,
+            ItemEnum::StructField(_) => Self::StructField,
+            ItemEnum::Variant(_) => Self::Variant,
+            ItemEnum::Constant { .. } => Self::Constant,
+            ItemEnum::Static(_) => Self::Static,
+            ItemEnum::Trait(_) => Self::Trait,
+            ItemEnum::AssocType { .. } => Self::AssocType,
+            ItemEnum::AssocConst { .. } => Self::AssocConst,
+            ItemEnum::Function(_) if in_trait_or_impl => Self::Method,
+            ItemEnum::Function(_) => Self::Function,
+            ItemEnum::TypeAlias(_) => Self::TypeAlias,
+            ItemEnum::Impl(impl_) => Self::Impl(ImplKind::from(item, impl_)),
+            ItemEnum::ExternType => Self::ExternType,
+            ItemEnum::TraitAlias(_) => Self::TraitAlias,
+        }
+    }
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Module => "module",
+            Self::ExternCrate => "extern_crate",
+            Self::Use => "use",
+            Self::Primitive => "primitive",
+            Self::Macro => "macro",
+            Self::ProcMacro => "proc_macro",
+            Self::Enum => "enum",
+            Self::Union => "union",
+            Self::Struct => "struct",
+            Self::StructField => "struct_field",
+            Self::Variant => "variant",
+            Self::Constant => "constant",
+            Self::Static => "static",
+            Self::Trait => "trait",
+            Self::AssocType => "assoc_type",
+            Self::AssocConst => "assoc_const",
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::TypeAlias => "type_alias",
+            Self::Impl(ImplKind::Inherent) => "inherent_impl",
+            Self::Impl(ImplKind::Trait) => "trait_impl",
+            Self::Impl(ImplKind::AutoDerived) => "auto_derived_impl",
+            Self::Impl(ImplKind::AutoTrait) => "auto_trait_impl",
+            Self::Impl(ImplKind::Blanket) => "blanket_impl",
+            Self::ExternType => "extern_type",
+            Self::TraitAlias => "trait_alias",
+        })
+    }
+}
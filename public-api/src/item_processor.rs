@@ -2,7 +2,7 @@ use super::nameable_item::NameableItem;
 use crate::{
     BuilderOptions as Options, PublicApi, crate_wrapper::CrateWrapper,
     intermediate_public_item::IntermediatePublicItem, path_component::PathComponent,
-    public_item::PublicItem, render::RenderingContext,
+    path_filter::PathFilter, public_item::PublicItem, query::Query, render::RenderingContext,
 };
 use rustdoc_types::{
     Attribute, Crate, Id, Impl, Item, ItemEnum, Module, Struct, StructKind, Type, Use, VariantKind,
@@ -29,6 +29,9 @@ struct UnprocessedItem<'c> {
 
     /// The Id of the item to process.
     id: Id,
+
+    /// If the item is reached via `pub use`, directly or via a glob.
+    reexport: bool,
 }
 
 /// Processes items to find more items and to figure out the path to each item.
@@ -78,11 +81,13 @@ impl<'c> ItemProcessor<'c> {
         parent_path: Vec<PathComponent<'c>>,
         parent_id: Option<Id>,
         id: Id,
+        reexport: bool,
     ) {
         self.work_queue.push_front(UnprocessedItem {
             parent_path,
             parent_id,
             id,
+            reexport,
         });
     }
 
@@ -141,6 +146,7 @@ impl<'c> ItemProcessor<'c> {
                     unprocessed_item.parent_path.clone(),
                     unprocessed_item.parent_id,
                     item_id,
+                    true,
                 );
             }
         } else {
@@ -164,7 +170,7 @@ impl<'c> ItemProcessor<'c> {
         &mut self,
         item: &'c Item,
         use_: &'c Use,
-        unprocessed_item: UnprocessedItem<'c>,
+        mut unprocessed_item: UnprocessedItem<'c>,
     ) {
         let mut actual_item = item;
 
@@ -173,6 +179,7 @@ impl<'c> ItemProcessor<'c> {
             .and_then(|id| self.get_item_if_not_in_path(&unprocessed_item.parent_path, id))
         {
             actual_item = used_item;
+            unprocessed_item.reexport = true;
         }
 
         self.process_item(unprocessed_item, actual_item, Some(use_.name.clone()));
@@ -198,7 +205,7 @@ impl<'c> ItemProcessor<'c> {
     /// that case.
     fn process_item_unless_recursive(
         &mut self,
-        unprocessed_item: UnprocessedItem<'c>,
+        mut unprocessed_item: UnprocessedItem<'c>,
         item: &'c Item,
         overridden_name: Option<String>,
     ) {
@@ -207,6 +214,8 @@ impl<'c> ItemProcessor<'c> {
             .iter()
             .any(|m| m.item.item.id == item.id)
         {
+            // Not a path to the item, so not a re-export of it either
+            unprocessed_item.reexport = false;
             let recursion_breaker = unprocessed_item.finish(
                 item,
                 Some(format!("<<{}>>", item.name.as_deref().unwrap_or(""))),
@@ -244,7 +253,7 @@ impl<'c> ItemProcessor<'c> {
         let impls = impls_for_item(item).into_iter().flatten();
 
         for &id in &children {
-            self.add_to_work_queue(finished_item.path().into(), Some(item.id), id);
+            self.add_to_work_queue(finished_item.path().into(), Some(item.id), id, false);
         }
 
         // As usual, impls are special. We want impl items to appear grouped
@@ -257,7 +266,7 @@ impl<'c> ItemProcessor<'c> {
             for a in &mut path {
                 a.hide = true;
             }
-            self.add_to_work_queue(path, Some(item.id), id);
+            self.add_to_work_queue(path, Some(item.id), id, false);
         }
 
         self.output.push(finished_item);
@@ -372,6 +381,7 @@ impl<'c> UnprocessedItem<'c> {
             },
             type_,
             hide: false,
+            reexport: self.reexport,
         });
 
         // Done
@@ -379,6 +389,32 @@ impl<'c> UnprocessedItem<'c> {
     }
 }
 
+/// If the item itself has `#[doc(hidden)]`. Note that such items are only part
+/// of rustdoc JSON built with `--document-hidden-items`.
+pub(crate) fn is_doc_hidden(item: &Item) -> bool {
+    item.attrs.iter().any(is_doc_hidden_attr)
+}
+
+pub(crate) fn is_doc_hidden_attr(attr: &Attribute) -> bool {
+    matches!(attr, Attribute::Other(other) if other.replace(' ', "") == "#[doc(hidden)]")
+}
+
+/// The feature of an item marked `#[unstable(feature = "...")]`, which only the
+/// standard library and other crates with `#![feature(staged_api)]` can do.
+pub(crate) fn unstable_feature(item: &Item) -> Option<String> {
+    item.attrs.iter().find_map(|attr| match attr {
+        Attribute::Other(other)
+            if other.starts_with("#[attr = Stability") && other.contains("level: Unstable") =>
+        {
+            crate::cfg::string_after(other, "feature: ")
+        }
+        Attribute::Other(other) if other.starts_with("#[unstable(") => {
+            crate::cfg::string_after(other, "feature = ")
+        }
+        _ => None,
+    })
+}
+
 /// In order for items in the output to be nicely grouped, we add a prefix to
 /// each item in the path to an item. That way, sorting on the name (with this
 /// prefix) will group items. But we don't want this prefix to be be visible to
@@ -429,8 +465,11 @@ pub(crate) fn sorting_prefix(item: &Item) -> u8 {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum ImplKind {
+/// What kind of `impl` an [`ItemKind::Impl`](crate::ItemKind::Impl) is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ImplKind {
     /// E.g. `impl Foo` or `impl<'a> Foo<'a>`
     Inherent,
 
@@ -448,7 +487,7 @@ pub(crate) enum ImplKind {
 }
 
 impl ImplKind {
-    fn from(impl_item: &Item, impl_: &Impl) -> Self {
+    pub(crate) fn from(impl_item: &Item, impl_: &Impl) -> Self {
         let has_blanket_impl = impl_.blanket_impl.is_some();
         let is_automatically_derived = impl_item.attrs.contains(&Attribute::AutomaticallyDerived);
 
@@ -485,22 +524,39 @@ pub fn impls_for_item(item: &Item) -> Option<&[Id]> {
     }
 }
 
-pub(crate) fn public_api_in_crate(crate_: &Crate, options: Options) -> super::PublicApi {
+pub(crate) fn public_api_in_crate(
+    crate_: &Crate,
+    options: Options,
+    path_filter: &PathFilter,
+    query: Option<&Query>,
+    unstable_features: &[String],
+) -> super::PublicApi {
     let mut item_processor = ItemProcessor::new(crate_, options);
-    item_processor.add_to_work_queue(vec![], None, crate_.root);
+    item_processor.add_to_work_queue(vec![], None, crate_.root, false);
     item_processor.run();
 
     let context = RenderingContext {
         crate_,
         id_to_items: item_processor.id_to_items(),
         options,
+        unstable_features,
     };
 
+    let reexport_infos = crate::reexport::reexport_infos(&item_processor.output);
+
     PublicApi {
         items: item_processor
             .output
             .iter()
-            .map(|item| PublicItem::from_intermediate_public_item(&context, item))
+            .zip(reexport_infos)
+            .filter(|(_, reexport)| !(options.collapse_reexports && reexport.is_alias))
+            .filter(|(item, _)| !(options.omit_doc_hidden && item.is_doc_hidden()))
+            .filter(|(item, _)| !(options.omit_unstable && item.is_unstable(unstable_features)))
+            .filter(|(item, _)| path_filter.keeps(item))
+            .filter(|(item, _)| query.is_none_or(|query| query.matches(&context, item)))
+            .map(|(item, reexport)| {
+                PublicItem::from_intermediate_public_item(&context, item, reexport)
+            })
             .collect::<Vec<_>>(),
         missing_item_ids: item_processor.crate_.missing_item_ids(),
     }
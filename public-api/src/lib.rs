@@ -30,6 +30,38 @@
 //! ```no_run
 #![doc = include_str!("../examples/diff_public_api.rs")]
 //! ```
+//!
+//! # Serialization
+//!
+//! With the `serde` cargo feature enabled, [`PublicApi`], [`PublicItem`],
+//! [`tokens::Token`] and [`diff::PublicApiDiff`] implement `Serialize` and
+//! `Deserialize`. This allows you to store a public API, e.g. as a build
+//! artifact, and later diff it with [`diff::PublicApiDiff::between`] without
+//! building rustdoc JSON again. In JSON, the format looks like this:
+//!
+//! ```txt
+//! PublicApi         {"items": [PublicItem, ...], "missing_item_ids": [u32, ...]}
+//! PublicItem        {"sortable_path": [String, ...], "tokens": [Token, ...],
+//!                    "parent_id": u32 | null, "id": u32, "reason_if_added": String,
+//!                    "doc_hidden": bool, "unstable": bool, "kind": ItemKind | null,
+//!                    "path": [String, ...], "canonical_path": String, "reexport": bool,
+//!                    "reexport_paths": [String, ...]}
+//! ItemKind          "module" | "struct" | "struct_field" | "function" | "method" | ...
+//!                   {"impl": "inherent" | "trait" | "auto_derived" | "auto_trait" | "blanket"}
+//! Token             {"kind": "symbol" | "qualifier" | "kind" | "identifier" |
+//!                            "annotation" | "self" | "function" | "lifetime" |
+//!                            "keyword" | "generic" | "primitive" | "type",
+//!                    "text": String}
+//!                   {"kind": "whitespace"}
+//! PublicApiDiff     {"removed": [PublicItem, ...], "changed": [ChangedPublicItem, ...],
+//!                    "deprecated": [ChangedPublicItem, ...], "moved": [ChangedPublicItem, ...],
+//!                    "added": [PublicItem, ...]}
+//! ChangedPublicItem {"old": PublicItem, "new": PublicItem}
+//! ```
+//!
+//! `reason_if_added` is a [`semver::SemverReason`] in `snake_case`, e.g.
+//! `"item_added"`. The format only changes in semver incompatible releases of
+//! this crate.
 
 // deny in CI, only warn here
 #![warn(missing_docs)]
@@ -39,18 +71,28 @@
 )]
 pub use rustdoc_types;
 
+mod cfg;
 mod crate_wrapper;
 mod error;
 mod intermediate_public_item;
+mod item_filter;
+mod item_kind;
 mod item_processor;
 mod nameable_item;
 mod path_component;
+mod path_filter;
 mod public_item;
+mod reexport;
 mod render;
+mod text_snapshot;
 pub mod tokens;
 
 pub mod diff;
+pub mod query;
+pub mod semver;
 
+use path_filter::PathFilter;
+use query::Query;
 use std::path::PathBuf;
 
 // Documented at the definition site so cargo doc picks it up
@@ -59,6 +101,12 @@ pub use error::{Error, Result};
 // Documented at the definition site so cargo doc picks it up
 pub use public_item::PublicItem;
 
+// Documented at the definition site so cargo doc picks it up
+pub use item_kind::ItemKind;
+
+// Documented at the definition site so cargo doc picks it up
+pub use item_processor::ImplKind;
+
 /// This constant defines the minimum version of nightly that is required in
 /// order for the rustdoc JSON output to be parsable by this library. Note that
 /// this library is implemented with stable Rust. But the rustdoc JSON that this
@@ -79,6 +127,9 @@ struct BuilderOptions {
     omit_auto_trait_impls: bool,
     omit_auto_derived_impls: bool,
     include_function_parameter_names: bool,
+    omit_doc_hidden: bool,
+    omit_unstable: bool,
+    collapse_reexports: bool,
 }
 
 /// Builds [`PublicApi`]s. See the [top level][`crate`] module docs for example
@@ -87,6 +138,9 @@ struct BuilderOptions {
 pub struct Builder {
     rustdoc_json: PathBuf,
     options: BuilderOptions,
+    path_filter: PathFilter,
+    query: Option<Query>,
+    unstable_features: Vec<String>,
 }
 
 impl Builder {
@@ -101,10 +155,16 @@ impl Builder {
             omit_auto_trait_impls: false,
             omit_auto_derived_impls: false,
             include_function_parameter_names: false,
+            omit_doc_hidden: false,
+            omit_unstable: false,
+            collapse_reexports: false,
         };
         Self {
             rustdoc_json: path.into(),
             options,
+            path_filter: PathFilter::default(),
+            query: None,
+            unstable_features: vec![],
         }
     }
 
@@ -186,6 +246,123 @@ impl Builder {
         self
     }
 
+    /// If `true`, items that are `#[doc(hidden)]`, or are inside an item that
+    /// is, are omitted from the output. See [`PublicItem::is_doc_hidden`].
+    ///
+    /// The default value is `false` so that the listed public API is complete
+    /// by default.
+    #[must_use]
+    pub fn omit_doc_hidden(mut self, omit_doc_hidden: bool) -> Self {
+        self.options.omit_doc_hidden = omit_doc_hidden;
+        self
+    }
+
+    /// If `true`, unstable items are omitted from the output, which leaves the
+    /// stable public API. See [`PublicItem::is_unstable`].
+    ///
+    /// The default value is `false` so that the listed public API is complete
+    /// by default.
+    #[must_use]
+    pub fn omit_unstable(mut self, omit_unstable: bool) -> Self {
+        self.options.omit_unstable = omit_unstable;
+        self
+    }
+
+    /// If `true`, items that are only reachable via a re-export alias, such as
+    /// `a::B` after `pub use c::B;` in module `a`, are omitted from the output.
+    /// Instead, the item at the [canonical
+    /// path](PublicItem::canonical_path) lists its aliases in
+    /// [`PublicItem::reexport_paths`], so that removing a re-export shows up as
+    /// a changed item rather than as a removed type. The aliases are rendered
+    /// after the item, e.g. `pub struct c::B // re-exported at a::B`.
+    ///
+    /// The default value is `false` so that the listed public API is complete
+    /// by default.
+    #[must_use]
+    pub fn collapse_reexports(mut self, collapse_reexports: bool) -> Self {
+        self.options.collapse_reexports = collapse_reexports;
+        self
+    }
+
+    /// Cargo features that enable unstable, typically nightly-only, items,
+    /// e.g. `nightly`. Items behind `#[cfg(feature = "nightly")]` or
+    /// `#[doc(cfg(feature = "nightly"))]` are then considered unstable, just
+    /// like items marked `#[unstable]`. Note that `#[cfg]`-ed out items are
+    /// only part of rustdoc JSON built with the feature enabled.
+    ///
+    /// The default value is empty.
+    #[must_use]
+    pub fn unstable_features(
+        mut self,
+        features: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.unstable_features = features.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// If not empty, only items whose path matches any of the given glob
+    /// patterns are included. In a pattern, `*` matches any part of a path
+    /// segment and `**` matches any number of path segments. For example,
+    /// `my_crate::client::**` matches `my_crate::client` and everything in it.
+    ///
+    /// Items of `impl`s have the path of the type they are for, so e.g. the
+    /// path of `impl Send for my_crate::Foo` is `my_crate::Foo`.
+    ///
+    /// The default value is empty, i.e. all items are included.
+    #[must_use]
+    pub fn only_paths(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.path_filter.only = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Items whose path matches any of the given glob patterns are omitted,
+    /// e.g. `my_crate::__private::**`. See [`Self::only_paths`] for the
+    /// syntax of the patterns.
+    ///
+    /// The default value is empty, i.e. no items are omitted.
+    #[must_use]
+    pub fn exclude_paths(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.path_filter.exclude = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// If set, only items that match all predicates of the [`Query`] are
+    /// included, e.g. only functions that return a `Result` with
+    /// `kind:fn returns:Result`. See [`Query`] for the predicates.
+    ///
+    /// The default value is `None`, i.e. all items are included.
+    #[must_use]
+    pub fn query(mut self, query: Option<Query>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Applies the filters of this builder, e.g. [`Self::omit_blanket_impls`],
+    /// [`Self::only_paths`] and [`Self::collapse_reexports`], to a
+    /// [`PublicApi`] that is already built, such as one read from a [text
+    /// snapshot](PublicApi::from_text_snapshot) or a deserialized structured
+    /// snapshot. This makes it comparable to a [`PublicApi`] built with this
+    /// builder. The rustdoc JSON of the builder is not used.
+    ///
+    /// The filters use what is known about each [`PublicItem`], so items of
+    /// an unknown [kind](PublicItem::kind) or [path](PublicItem::path), like
+    /// items read from a text snapshot, are only omitted if they are
+    /// annotated with e.g. `#[doc(hidden)]`.
+    ///
+    /// # Errors
+    ///
+    /// If a [`Self::query`] is set, since queries need the rustdoc JSON.
+    pub fn apply_filters(&self, public_api: PublicApi) -> Result<PublicApi> {
+        if self.query.is_some() {
+            return Err(Error::QueryNeedsRustdocJson);
+        }
+        Ok(item_filter::apply(
+            public_api,
+            self.options,
+            &self.path_filter,
+        ))
+    }
+
     /// Builds [`PublicApi`]. See the [top level][`crate`] module docs for
     /// example code.
     ///
@@ -194,7 +371,13 @@ impl Builder {
     /// E.g. if the [JSON](Builder::from_rustdoc_json) is invalid or if the file
     /// can't be read.
     pub fn build(self) -> Result<PublicApi> {
-        from_rustdoc_json_str(std::fs::read_to_string(self.rustdoc_json)?, self.options)
+        from_rustdoc_json_str(
+            std::fs::read_to_string(self.rustdoc_json)?,
+            self.options,
+            &self.path_filter,
+            self.query.as_ref(),
+            &self.unstable_features,
+        )
     }
 }
 
@@ -228,6 +411,7 @@ impl Builder {
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive] // More fields might be added in the future
 pub struct PublicApi {
     /// The items that constitutes the public API. An "item" is for example a
@@ -240,6 +424,33 @@ pub struct PublicApi {
 }
 
 impl PublicApi {
+    /// Parses a text snapshot of a public API, such as one written by
+    /// [`Self::assert_eq_or_update()`], back into a [`PublicApi`]. This allows
+    /// you to diff against a snapshot without building rustdoc JSON for it.
+    ///
+    /// A text snapshot only contains the rendered text of each item, so the
+    /// [`PublicItem`]s have no meaningful [`id`](PublicItem::id) or
+    /// [`parent_id`](PublicItem::parent_id), and their [tokens](PublicItem::tokens)
+    /// are guessed from the text. To get a meaningful diff against a public API
+    /// built from rustdoc JSON, first reduce it to text in the same way:
+    ///
+    /// ```no_run
+    /// # let snapshot = String::new();
+    /// # let rustdoc_json = std::path::PathBuf::new();
+    /// use public_api::{PublicApi, diff::PublicApiDiff};
+    ///
+    /// let old = PublicApi::from_text_snapshot(&snapshot);
+    /// let new = public_api::Builder::from_rustdoc_json(rustdoc_json).build()?;
+    /// let new = PublicApi::from_text_snapshot(&new.to_string());
+    ///
+    /// let diff = PublicApiDiff::between(old, new);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn from_text_snapshot(snapshot: &str) -> Self {
+        text_snapshot::public_api_from_text_snapshot(snapshot)
+    }
+
     /// Returns an iterator over all public items in the public API
     pub fn items(&self) -> impl Iterator<Item = &'_ PublicItem> {
         self.items.iter()
@@ -283,7 +494,11 @@ impl PublicApi {
 impl std::fmt::Display for PublicApi {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for item in self.items() {
-            writeln!(f, "{item}")?;
+            write!(f, "{item}")?;
+            if !item.reexport_paths().is_empty() {
+                write!(f, " // re-exported at {}", item.reexport_paths().join(", "))?;
+            }
+            writeln!(f)?;
         }
         Ok(())
     }
@@ -292,10 +507,19 @@ impl std::fmt::Display for PublicApi {
 fn from_rustdoc_json_str(
     rustdoc_json_str: impl AsRef<str>,
     options: BuilderOptions,
+    path_filter: &PathFilter,
+    query: Option<&Query>,
+    unstable_features: &[String],
 ) -> Result<PublicApi> {
     let crate_ = deserialize_without_recursion_limit(rustdoc_json_str.as_ref())?;
 
-    let mut public_api = item_processor::public_api_in_crate(&crate_, options);
+    let mut public_api = item_processor::public_api_in_crate(
+        &crate_,
+        options,
+        path_filter,
+        query,
+        unstable_features,
+    );
 
     if options.sorted {
         public_api.items.sort_by(PublicItem::grouping_cmp);
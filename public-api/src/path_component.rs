@@ -20,4 +20,8 @@ pub struct PathComponent<'c> {
 
     /// If `true`, do not render this path component to users.
     pub hide: bool,
+
+    /// If `true`, the item was reached via `pub use`, i.e. this path component
+    /// is a re-export of the item rather than where it is defined.
+    pub reexport: bool,
 }
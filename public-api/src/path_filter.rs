@@ -0,0 +1,100 @@
+//! Filters items on their path with glob patterns. See
+//! [`crate::Builder::only_paths`] and [`crate::Builder::exclude_paths`].
+
+use crate::intermediate_public_item::IntermediatePublicItem;
+
+/// Glob patterns to include and exclude items with.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PathFilter {
+    pub(crate) only: Vec<String>,
+    pub(crate) exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// If the item shall be part of the public API. Items of `impl`s have the
+    /// path of the type or trait the `impl` belongs to, e.g. the path of a
+    /// trait method implemented for `a::B` is `a::B::method`, and the path of
+    /// the `impl` itself is `a::B`.
+    pub(crate) fn keeps(&self, item: &IntermediatePublicItem<'_>) -> bool {
+        if self.only.is_empty() && self.exclude.is_empty() {
+            return true;
+        }
+
+        let path: Vec<&str> = item.path().iter().filter_map(|p| p.item.name()).collect();
+        self.keeps_path(&path)
+    }
+
+    /// Like [`Self::keeps`], but for the path of an item as segments.
+    pub(crate) fn keeps_path(&self, path: &[&str]) -> bool {
+        let matches_any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| matches(&pattern.split("::").collect::<Vec<_>>(), path))
+        };
+
+        (self.only.is_empty() || matches_any(&self.only)) && !matches_any(&self.exclude)
+    }
+}
+
+/// If `path` matches `pattern`, where `**` matches any number of path
+/// segments, including none, and `*` matches any part of a single segment.
+pub(crate) fn matches(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", pattern_rest)), _) => {
+            matches(pattern_rest, path)
+                || path
+                    .split_first()
+                    .is_some_and(|(_, path_rest)| matches(pattern, path_rest))
+        }
+        (Some((pattern_segment, pattern_rest)), Some((segment, path_rest))) => {
+            segment_matches(pattern_segment, segment) && matches(pattern_rest, path_rest)
+        }
+        _ => false,
+    }
+}
+
+/// If `segment` matches `pattern`, where `*` matches any number of characters.
+pub(crate) fn segment_matches(pattern: &str, segment: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == segment,
+        Some((prefix, pattern_rest)) => {
+            let Some(rest) = segment.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=rest.len())
+                .filter(|&i| rest.is_char_boundary(i))
+                .any(|i| segment_matches(pattern_rest, &rest[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matching() {
+        for (pattern, path, expected) in [
+            ("a::b", "a::b", true),
+            ("a::b", "a::b::c", false),
+            ("a::*", "a::b", true),
+            ("a::*", "a", false),
+            ("a::*", "a::b::c", false),
+            ("a::**", "a", true),
+            ("a::**", "a::b::c", true),
+            ("a::**::c", "a::c", true),
+            ("a::**::c", "a::b::x::c", true),
+            ("a::**::c", "a::b::x", false),
+            ("**::__private::**", "a::b::__private::f", true),
+            ("a::__*", "a::__private", true),
+            ("a::__*", "a::private", false),
+            ("a::*_ext", "a::io_ext", true),
+            ("a::*_ext", "a::io_ext2", false),
+        ] {
+            let pattern: Vec<&str> = pattern.split("::").collect();
+            let path: Vec<&str> = path.split("::").collect();
+            assert_eq!(matches(&pattern, &path), expected, "{pattern:?} {path:?}");
+        }
+    }
+}
@@ -4,7 +4,10 @@ use std::fmt::Display;
 use std::hash::Hash;
 
 use crate::intermediate_public_item::IntermediatePublicItem;
+use crate::item_kind::ItemKind;
+use crate::reexport::ReexportInfo;
 use crate::render::RenderingContext;
+use crate::semver::SemverReason;
 use crate::tokens::Token;
 use crate::tokens::tokens_to_string;
 
@@ -19,6 +22,7 @@ pub(crate) type PublicItemPath = Vec<String>;
 /// also implements [`Ord`], but how items are ordered are not stable yet, and
 /// will change in later versions.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PublicItem {
     /// Read [`crate::item_processor::sorting_prefix()`] docs for more info
     pub(crate) sortable_path: PublicItemPath,
@@ -31,18 +35,64 @@ pub struct PublicItem {
 
     /// The [`Id`] to which this public item corresponds
     pub(crate) id: Id,
+
+    /// Read [`crate::semver::reason_if_added()`] docs for more info
+    pub(crate) reason_if_added: SemverReason,
+
+    /// See [`Self::is_doc_hidden()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) doc_hidden: bool,
+
+    /// See [`Self::is_unstable()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) unstable: bool,
+
+    /// See [`Self::kind()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) kind: Option<ItemKind>,
+
+    /// See [`Self::path()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) path: Vec<String>,
+
+    /// See [`Self::canonical_path()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) canonical_path: String,
+
+    /// See [`Self::is_reexport()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) reexport: bool,
+
+    /// See [`Self::reexport_paths()`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) reexport_paths: Vec<String>,
 }
 
 impl PublicItem {
     pub(crate) fn from_intermediate_public_item(
         context: &RenderingContext,
         public_item: &IntermediatePublicItem<'_>,
+        reexport: ReexportInfo,
     ) -> PublicItem {
+        let reexport_paths = if context.options.collapse_reexports {
+            reexport.alias_paths
+        } else {
+            vec![]
+        };
+
         PublicItem {
             sortable_path: public_item.sortable_path(context),
             tokens: public_item.render_token_stream(context),
             parent_id: public_item.parent_id(),
             id: public_item.id(),
+            reason_if_added: crate::semver::reason_if_added(public_item),
+            doc_hidden: public_item.is_doc_hidden(),
+            unstable: public_item.is_unstable(context.unstable_features),
+            kind: Some(public_item.kind()),
+            path: public_item.path_names(),
+            canonical_path: reexport.canonical_path,
+            reexport: reexport.is_alias,
+            reexport_paths,
         }
     }
 
@@ -58,11 +108,139 @@ impl PublicItem {
         self.id
     }
 
+    /// If the item is `#[doc(hidden)]`, or is inside an item that is, e.g. a
+    /// function in a `#[doc(hidden)]` module. Such items are typically only
+    /// public for the sake of macros, and not meant to be used directly. They
+    /// are only part of rustdoc JSON built with `--document-hidden-items`.
+    #[must_use]
+    pub fn is_doc_hidden(&self) -> bool {
+        self.doc_hidden
+    }
+
+    /// If the item is `#[unstable]`, or is inside an item that is. Items that
+    /// can only be built with any of the
+    /// [`Builder::unstable_features`](crate::Builder::unstable_features)
+    /// enabled are unstable too. Breaking changes to unstable items are
+    /// typically allowed in any release.
+    #[must_use]
+    pub fn is_unstable(&self) -> bool {
+        self.unstable
+    }
+
+    /// If the item is `#[deprecated]`. The `since` and `note` of the
+    /// deprecation, if any, are part of the rendered annotation.
+    #[must_use]
+    pub fn is_deprecated(&self) -> bool {
+        self.tokens.iter().any(crate::semver::is_deprecation)
+    }
+
+    /// What kind of item this is, e.g. [`ItemKind::Method`].
+    ///
+    /// `None` if not known, e.g. for items deserialized from a public API
+    /// serialized by an older version of this library, and for items read from
+    /// a text snapshot.
+    #[must_use]
+    pub fn kind(&self) -> Option<ItemKind> {
+        self.kind
+    }
+
+    /// The path of the item as segments, e.g. `["a", "B", "f"]` for `pub fn
+    /// a::B::f()`, without generic args. Items of `impl`s have the path of the
+    /// type or trait the `impl` belongs to, e.g. `["a", "B", "clone"]` for
+    /// `Clone::clone` of `a::B`, and so does the `impl` itself.
+    ///
+    /// Empty if not known, e.g. for items deserialized from a public API
+    /// serialized by an older version of this library, and for `impl`s read
+    /// from a text snapshot.
+    #[must_use]
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The path where the item is defined, or where it is re-exported if it
+    /// is only reachable via re-exports. For example, after `pub use
+    /// c::B;` in module `a`, the canonical path of both `c::B` and `a::B` is
+    /// `c::B`, and for a field of `a::B` it is `c::B::field`. Items of `impl`s
+    /// have the path of the type or trait the `impl` belongs to.
+    ///
+    /// Empty if not known, e.g. for items deserialized from a public API
+    /// serialized by an older version of this library, and for `impl`s read
+    /// from a text snapshot.
+    #[must_use]
+    pub fn canonical_path(&self) -> &str {
+        &self.canonical_path
+    }
+
+    /// If the item is reached via a re-export alias, e.g. `a::B` after `pub
+    /// use c::B;` in module `a`, rather than by its [canonical
+    /// path](Self::canonical_path). Fields, methods and `impl`s of an alias
+    /// are aliases too.
+    #[must_use]
+    pub fn is_reexport(&self) -> bool {
+        self.reexport
+    }
+
+    /// The re-export aliases of the item, e.g. `["a::B"]` for `c::B` after
+    /// `pub use c::B;` in module `a`. Only set with
+    /// [`Builder::collapse_reexports`](crate::Builder::collapse_reexports),
+    /// which omits the aliases themselves from the public API. The aliases are
+    /// not part of the [`tokens`](Self::tokens), so they do not make items
+    /// unequal, but [`PublicApiDiff`](crate::diff::PublicApiDiff) reports
+    /// changes to them.
+    #[must_use]
+    pub fn reexport_paths(&self) -> &[String] {
+        &self.reexport_paths
+    }
+
     /// The rendered item as a stream of [`Token`]s
     pub fn tokens(&self) -> impl Iterator<Item = &Token> {
         self.tokens.iter()
     }
 
+    /// Finds the path of the item in its rendered tokens. Items are rendered
+    /// as annotations and qualifiers, then the keywords that declare the item,
+    /// and then its path. Returns `None` for `impl`s, which have no path.
+    pub(crate) fn rendered_path(&self) -> Option<RenderedPath<'_>> {
+        let tokens = self.tokens.as_slice();
+        let mut start = 0;
+        while let Some(token) = tokens.get(start) {
+            match token {
+                Token::Keyword(text) if text == "impl" => return None,
+                Token::Kind(_) | Token::Annotation(_) | Token::Qualifier(_) | Token::Whitespace => {
+                    start += 1;
+                }
+                _ => break,
+            }
+        }
+
+        let mut names = vec![];
+        let mut index = start;
+        let mut end = start;
+        while let Some(
+            Token::Identifier(name)
+            | Token::Type(name)
+            | Token::Function(name)
+            | Token::Primitive(name)
+            | Token::Generic(name)
+            | Token::Self_(name),
+        ) = tokens.get(index)
+        {
+            names.push(name.as_str());
+            end = index + 1;
+            index = skip_generic_args(tokens, end);
+            match tokens.get(index) {
+                Some(Token::Symbol(separator)) if separator == "::" => index += 1,
+                _ => break,
+            }
+        }
+
+        (!names.is_empty()).then(|| RenderedPath {
+            names,
+            before: &tokens[..start],
+            after: &tokens[end..],
+        })
+    }
+
     /// Special version of [`cmp`](Ord::cmp) that is used to sort public items in a way that
     /// makes them grouped logically. For example, struct fields will be put
     /// right after the struct they are part of.
@@ -108,6 +286,49 @@ impl Display for PublicItem {
     }
 }
 
+/// The path of an item as rendered. See [`PublicItem::rendered_path`].
+pub(crate) struct RenderedPath<'a> {
+    /// The names in the path, e.g. `["a", "B", "f"]` for `pub fn a::B::f()`.
+    /// Generic args are not part of the names.
+    pub(crate) names: Vec<&'a str>,
+
+    /// The tokens before the path, e.g. `pub fn `.
+    pub(crate) before: &'a [Token],
+
+    /// The tokens after the last name in the path, e.g. `()`.
+    pub(crate) after: &'a [Token],
+}
+
+/// Returns the index after `<...>` if `tokens` has it at `index`, otherwise
+/// `index`. Symbols can be e.g. `>>` so we count angle brackets rather than
+/// tokens. The `>` of `->` and `=>`, e.g. in `F: Fn() -> T`, is not an angle
+/// bracket.
+fn skip_generic_args(tokens: &[Token], index: usize) -> usize {
+    if tokens.get(index).map(Token::text) != Some("<") {
+        return index;
+    }
+
+    let mut depth = 0;
+    for (offset, token) in tokens[index..].iter().enumerate() {
+        if let Token::Symbol(symbol) = token
+            && symbol != "->"
+            && symbol != "=>"
+        {
+            for c in symbol.chars() {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+            }
+            if depth == 0 {
+                return index + offset + 1;
+            }
+        }
+    }
+    tokens.len()
+}
+
 /// Returns `None` if two items are equal. Otherwise their ordering is returned.
 fn different_or_none<T: Ord>(a: &T, b: &T) -> Option<Ordering> {
     match a.cmp(b) {
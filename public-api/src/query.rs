@@ -0,0 +1,343 @@
+//! Filters items with structured predicates, like `kind:fn returns:Result`.
+//! See [`Query`].
+
+use rustdoc_types::{Function, ItemEnum, Type};
+
+use crate::intermediate_public_item::IntermediatePublicItem;
+use crate::item_kind::ItemKind;
+use crate::path_filter;
+use crate::render::RenderingContext;
+use crate::tokens::{Token, tokens_to_string};
+use crate::{Error, Result};
+
+/// Predicates that items must match to be included in the public API. Use
+/// with [`crate::Builder::query`]. Each predicate is `key:value`, and an item
+/// must match all of them:
+///
+/// * `kind:fn` matches items of that [`ItemKind`], written like it is
+///   displayed, e.g. `struct_field` or `trait_impl`. `fn` matches functions
+///   and methods, `mod` modules, `const` constants and associated constants,
+///   `type` type aliases and associated types, `field` struct fields and
+///   `impl` all kinds of `impl`s. Several kinds can be given, e.g.
+///   `kind:struct,enum`.
+/// * `path:crate::io::*` matches items whose path matches the glob pattern,
+///   like with [`crate::Builder::only_paths`]. A leading `crate` matches the
+///   name of the crate.
+/// * `name:read_*` matches items whose name matches the glob pattern.
+/// * `returns:Result` matches functions whose return type contains the given
+///   type. Paths can be left out, so `returns:impl Future` matches `-> impl
+///   core::future::Future<Output = u8>`. `async fn`s return `impl Future`.
+/// * `impl:Serialize` matches `impl`s of the given trait, with or without
+///   its path.
+/// * `unsafe:true` matches `unsafe` functions, traits and `impl`s, and
+///   `unsafe:false` all other items.
+/// * `self:mut` matches methods that take `&mut self`. Other values are `ref`
+///   for `&self`, `value` for `self`, `other` for e.g. `self: Box<Self>`,
+///   `any` for any of them and `none` for items without `self`.
+/// * `text:Copy` matches items whose rendered text contains the given text.
+///
+/// Types and text match whole words, so `returns:Result` does not match
+/// `-> MyResult`.
+#[derive(Clone, Debug)]
+pub struct Query {
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Clone, Debug)]
+enum Predicate {
+    Kind(Vec<String>),
+    Path(String),
+    Name(String),
+    Returns(String),
+    Impl(String),
+    Unsafe(bool),
+    Self_(SelfKind),
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelfKind {
+    Ref,
+    Mut,
+    Value,
+    Other,
+    Any,
+    None,
+}
+
+impl Query {
+    /// Parses one predicate per string, e.g. `["kind:fn", "returns:Result"]`.
+    ///
+    /// # Errors
+    ///
+    /// If a predicate is not `key:value`, if the key is unknown, or if the
+    /// value is not valid for the key.
+    pub fn new(predicates: impl IntoIterator<Item = impl AsRef<str>>) -> Result<Self> {
+        let predicates = predicates
+            .into_iter()
+            .map(|predicate| Predicate::parse(predicate.as_ref()))
+            .collect::<Result<_>>()?;
+        Ok(Self { predicates })
+    }
+
+    /// If `item` matches all predicates.
+    pub(crate) fn matches(
+        &self,
+        context: &RenderingContext,
+        item: &IntermediatePublicItem<'_>,
+    ) -> bool {
+        self.predicates
+            .iter()
+            .all(|predicate| predicate.matches(context, item))
+    }
+}
+
+impl Predicate {
+    fn parse(predicate: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidQuery(format!("`{predicate}`: {reason}"));
+        let Some((key, value)) = predicate.split_once(':') else {
+            return Err(invalid("expected `key:value`"));
+        };
+        if value.is_empty() {
+            return Err(invalid("expected a value"));
+        }
+
+        Ok(match key {
+            "kind" => {
+                let kinds: Vec<String> = value.split(',').map(String::from).collect();
+                if let Some(kind) = kinds.iter().find(|kind| !is_kind(kind)) {
+                    return Err(invalid(&format!(
+                        "unknown kind `{kind}`, expected e.g. `fn`, `struct` or `trait_impl`"
+                    )));
+                }
+                Self::Kind(kinds)
+            }
+            "path" => Self::Path(value.to_owned()),
+            "name" => Self::Name(value.to_owned()),
+            "returns" => Self::Returns(value.to_owned()),
+            "impl" => Self::Impl(value.to_owned()),
+            "unsafe" => Self::Unsafe(match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid("expected `true` or `false`")),
+            }),
+            "self" => Self::Self_(match value {
+                "ref" => SelfKind::Ref,
+                "mut" => SelfKind::Mut,
+                "value" => SelfKind::Value,
+                "other" => SelfKind::Other,
+                "any" => SelfKind::Any,
+                "none" => SelfKind::None,
+                _ => {
+                    return Err(invalid(
+                        "expected `ref`, `mut`, `value`, `other`, `any` or `none`",
+                    ));
+                }
+            }),
+            "text" => Self::Text(value.to_owned()),
+            _ => {
+                return Err(invalid(
+                    "unknown key, expected `kind`, `path`, `name`, `returns`, `impl`, `unsafe`, `self` or `text`",
+                ));
+            }
+        })
+    }
+
+    fn matches(&self, context: &RenderingContext, item: &IntermediatePublicItem<'_>) -> bool {
+        match self {
+            Self::Kind(kinds) => kinds.iter().any(|kind| kind_matches(kind, item.kind())),
+            Self::Path(pattern) => {
+                let path = item.path_names();
+                let mut pattern: Vec<&str> = pattern.split("::").collect();
+                if pattern.first() == Some(&"crate")
+                    && let Some(crate_name) = path.first()
+                {
+                    pattern[0] = crate_name;
+                }
+                let path: Vec<&str> = path.iter().map(String::as_str).collect();
+                path_filter::matches(&pattern, &path)
+            }
+            Self::Name(pattern) => item
+                .item()
+                .name
+                .as_deref()
+                .is_some_and(|name| path_filter::segment_matches(pattern, name)),
+            Self::Returns(type_) => function(item).is_some_and(|function| {
+                let mut output = match &function.sig.output {
+                    Some(output) => context.render_type(output),
+                    None => vec![Token::symbol("()")],
+                };
+                if function.header.is_async {
+                    let mut future = vec![
+                        Token::keyword("impl"),
+                        Token::Whitespace,
+                        Token::type_("Future"),
+                        Token::symbol("<"),
+                        Token::identifier("Output"),
+                        Token::Whitespace,
+                        Token::symbol("="),
+                        Token::Whitespace,
+                    ];
+                    future.append(&mut output);
+                    future.push(Token::symbol(">"));
+                    output = future;
+                }
+                tokens_contain(&output, type_)
+            }),
+            Self::Impl(trait_) => match &item.item().inner {
+                ItemEnum::Impl(impl_) => impl_.trait_.as_ref().is_some_and(|path| {
+                    let rendered = context.render_resolved_path(&rustdoc_types::Path {
+                        args: None,
+                        ..path.clone()
+                    });
+                    let rendered = tokens_to_string(&rendered);
+                    rendered == *trait_ || rendered.ends_with(&format!("::{trait_}"))
+                }),
+                _ => false,
+            },
+            Self::Unsafe(unsafe_) => {
+                let is_unsafe = match &item.item().inner {
+                    ItemEnum::Function(function) => function.header.is_unsafe,
+                    ItemEnum::Trait(trait_) => trait_.is_unsafe,
+                    ItemEnum::Impl(impl_) => impl_.is_unsafe,
+                    _ => false,
+                };
+                is_unsafe == *unsafe_
+            }
+            Self::Self_(self_kind) => {
+                let actual = function(item).map_or(SelfKind::None, self_kind_of);
+                match self_kind {
+                    SelfKind::Any => actual != SelfKind::None,
+                    _ => actual == *self_kind,
+                }
+            }
+            Self::Text(text) => tokens_contain(&item.render_token_stream(context), text),
+        }
+    }
+}
+
+/// Kinds that match several [`ItemKind`]s. See [`kind_matches`].
+const KIND_ALIASES: [&str; 6] = ["fn", "mod", "const", "type", "field", "impl"];
+
+/// If `kind` is an alias or the name of an [`ItemKind`].
+fn is_kind(kind: &str) -> bool {
+    KIND_ALIASES.contains(&kind) || ItemKind::ALL.iter().any(|k| k.to_string() == kind)
+}
+
+fn kind_matches(kind: &str, actual: ItemKind) -> bool {
+    match kind {
+        "fn" => matches!(actual, ItemKind::Function | ItemKind::Method),
+        "mod" => actual == ItemKind::Module,
+        "const" => matches!(actual, ItemKind::Constant | ItemKind::AssocConst),
+        "type" => matches!(actual, ItemKind::TypeAlias | ItemKind::AssocType),
+        "field" => actual == ItemKind::StructField,
+        "impl" => matches!(actual, ItemKind::Impl(_)),
+        _ => actual.to_string() == kind,
+    }
+}
+
+fn function<'c>(item: &IntermediatePublicItem<'c>) -> Option<&'c Function> {
+    match &item.item().inner {
+        ItemEnum::Function(function) => Some(function),
+        _ => None,
+    }
+}
+
+fn self_kind_of(function: &Function) -> SelfKind {
+    match function.sig.inputs.first() {
+        Some((name, type_)) if name == "self" => match type_ {
+            Type::Generic(name) if name == "Self" => SelfKind::Value,
+            Type::BorrowedRef {
+                is_mutable, type_, ..
+            } if matches!(type_.as_ref(), Type::Generic(name) if name == "Self") => {
+                if *is_mutable {
+                    SelfKind::Mut
+                } else {
+                    SelfKind::Ref
+                }
+            }
+            _ => SelfKind::Other,
+        },
+        _ => SelfKind::None,
+    }
+}
+
+/// If the rendered `tokens` contain `text` as whole words, either as rendered
+/// or with paths left out, e.g. `impl Future` in `impl
+/// core::future::Future<Output = u8>`.
+fn tokens_contain(tokens: &[Token], text: &str) -> bool {
+    contains_words(&tokens_to_string(tokens), text)
+        || contains_words(&tokens_to_string(&without_paths(tokens)), text)
+}
+
+/// Leaves out the path of paths, e.g. turns `core::future::Future` into
+/// `Future`.
+fn without_paths(tokens: &[Token]) -> Vec<Token> {
+    let mut output = vec![];
+    let mut in_path = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let next_is_separator =
+            matches!(tokens.get(index + 1), Some(Token::Symbol(s)) if s == "::");
+        match token {
+            Token::Identifier(_) | Token::Type(_) if next_is_separator => in_path = true,
+            Token::Symbol(s) if s == "::" && in_path => in_path = false,
+            _ => {
+                in_path = false;
+                output.push(token.clone());
+            }
+        }
+    }
+    output
+}
+
+/// If `text` is in `haystack`, and not as part of a longer word.
+fn contains_words(haystack: &str, text: &str) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    haystack.match_indices(text).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + text.len()..].chars().next();
+        let starts_word = !text.starts_with(is_word_char) || !before.is_some_and(is_word_char);
+        let ends_word = !text.ends_with(is_word_char) || !after.is_some_and(is_word_char);
+        starts_word && ends_word
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_predicates() {
+        for predicate in [
+            "kind",
+            "kind:",
+            "kind:strcut",
+            "kind:fn,functions",
+            "colour:red",
+            "unsafe:yes",
+            "self:box",
+        ] {
+            assert!(Query::new([predicate]).is_err(), "{predicate}");
+        }
+        assert!(Query::new(["kind:fn", "returns:impl Future", "self:mut"]).is_ok());
+        assert!(Query::new(["kind:struct,trait_impl,assoc_const,field"]).is_ok());
+    }
+
+    #[test]
+    fn word_matching() {
+        for (haystack, text, expected) in [
+            ("core::result::Result<u8, E>", "Result", true),
+            ("my_crate::MyResult", "Result", false),
+            ("my_crate::Result2", "Result", false),
+            ("std::io::Result<()>", "io::Result", true),
+            ("impl Future<Output = u8>", "impl Future", true),
+            ("&mut Self", "&mut", true),
+        ] {
+            assert_eq!(
+                contains_words(haystack, text),
+                expected,
+                "{haystack} {text}"
+            );
+        }
+    }
+}
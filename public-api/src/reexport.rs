@@ -0,0 +1,132 @@
+//! Tells re-export aliases apart from canonical paths. An item that is
+//! re-exported with `pub use` is reachable by several paths, and the item
+//! processor lists it once per path. See [`crate::PublicItem::is_reexport`].
+
+use std::collections::HashMap;
+
+use rustdoc_types::Id;
+
+use crate::intermediate_public_item::IntermediatePublicItem;
+use crate::path_component::PathComponent;
+
+/// How an item relates to other paths of the same item.
+#[derive(Debug, Default)]
+pub(crate) struct ReexportInfo {
+    /// The canonical path of the item, e.g. `c::B` for `a::B` after `pub use
+    /// c::B` in `a`. For items that are not re-export aliases, their own path.
+    pub(crate) canonical_path: String,
+
+    /// If the path of the item, or of an item in its path, is a re-export
+    /// alias of an item that has another, canonical, path.
+    pub(crate) is_alias: bool,
+
+    /// For canonical items, the re-export aliases of the item itself, e.g.
+    /// `["a::B"]` for `c::B`. Items in alias modules are not included.
+    pub(crate) alias_paths: Vec<String>,
+}
+
+/// Returns a [`ReexportInfo`] for each item in `items`, in the same order.
+///
+/// Of all paths to an item, the canonical path is the one with the fewest
+/// re-exports in it, then the shortest, and then the first in alphabetical
+/// order. So an item defined in a public module has that path as its canonical
+/// path, while an item defined in a private module and re-exported once has
+/// the re-export as its canonical path.
+pub(crate) fn reexport_infos(items: &[IntermediatePublicItem<'_>]) -> Vec<ReexportInfo> {
+    let canonical = canonical_items(items);
+
+    let mut infos: Vec<ReexportInfo> = items
+        .iter()
+        .map(|item| {
+            let path = item.path();
+            let Some(last_alias) = (0..path.len())
+                .rev()
+                .find(|&index| is_alias_component(&canonical, items, &path[..=index]))
+            else {
+                return ReexportInfo {
+                    canonical_path: path_string(path),
+                    ..ReexportInfo::default()
+                };
+            };
+
+            let aliased = &items[canonical[&path[last_alias].item.item.id]];
+            let mut names = names_of(aliased.path());
+            names.extend(names_of(&path[last_alias + 1..]));
+            ReexportInfo {
+                canonical_path: names.join("::"),
+                is_alias: true,
+                alias_paths: vec![],
+            }
+        })
+        .collect();
+
+    for item in items {
+        if is_alias_component(&canonical, items, item.path()) {
+            let index = canonical[&item.item().id];
+            infos[index].alias_paths.push(path_string(item.path()));
+        }
+    }
+    for info in &mut infos {
+        info.alias_paths.sort();
+        info.alias_paths.dedup();
+    }
+
+    infos
+}
+
+/// Maps the [`Id`] of each item to the index in `items` of its canonical path.
+fn canonical_items(items: &[IntermediatePublicItem<'_>]) -> HashMap<Id, usize> {
+    let mut canonical: HashMap<Id, usize> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        canonical
+            .entry(item.item().id)
+            .and_modify(|best| {
+                if canonical_key(item) < canonical_key(&items[*best]) {
+                    *best = index;
+                }
+            })
+            .or_insert(index);
+    }
+    canonical
+}
+
+/// Lower is more canonical.
+fn canonical_key<'a>(item: &'a IntermediatePublicItem<'_>) -> (usize, usize, Vec<&'a str>) {
+    let path = item.path();
+    let names = names_of(path);
+    (
+        path.iter().filter(|p| p.reexport).count(),
+        names.len(),
+        names,
+    )
+}
+
+/// If the last component of `path` is a re-export of an item whose canonical
+/// path is a different one.
+fn is_alias_component(
+    canonical: &HashMap<Id, usize>,
+    items: &[IntermediatePublicItem<'_>],
+    path: &[PathComponent<'_>],
+) -> bool {
+    let Some(last) = path.last().filter(|last| last.reexport) else {
+        return false;
+    };
+    canonical
+        .get(&last.item.item.id)
+        .is_some_and(|&index| !same_path(items[index].path(), path))
+}
+
+fn same_path(a: &[PathComponent<'_>], b: &[PathComponent<'_>]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.item.item.id == b.item.item.id && a.item.name() == b.item.name())
+}
+
+fn names_of<'a>(path: &'a [PathComponent<'_>]) -> Vec<&'a str> {
+    path.iter().filter_map(|p| p.item.name()).collect()
+}
+
+fn path_string(path: &[PathComponent<'_>]) -> String {
+    names_of(path).join("::")
+}
@@ -8,10 +8,10 @@ use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
 
 use rustdoc_types::{
     Abi, AssocItemConstraint, AssocItemConstraintKind, Attribute, AttributeRepr, Constant, Crate,
-    FunctionHeader, FunctionPointer, FunctionSignature, GenericArg, GenericArgs, GenericBound,
-    GenericParamDef, GenericParamDefKind, Generics, Id, Impl, Item, ItemEnum, MacroKind, Path,
-    PolyTrait, ReprKind, StructKind, Term, Trait, TraitBoundModifier, Type, VariantKind,
-    WherePredicate,
+    Deprecation, FunctionHeader, FunctionPointer, FunctionSignature, GenericArg, GenericArgs,
+    GenericBound, GenericParamDef, GenericParamDefKind, Generics, Id, Impl, Item, ItemEnum,
+    MacroKind, Path, PolyTrait, ReprKind, StructKind, Term, Trait, TraitBoundModifier, Type,
+    VariantKind, WherePredicate,
 };
 
 /// A simple macro to write `Token::Whitespace` in less characters.
@@ -32,6 +32,9 @@ pub struct RenderingContext<'c> {
     pub id_to_items: HashMap<&'c Id, Vec<&'c IntermediatePublicItem<'c>>>,
 
     pub(crate) options: Options,
+
+    /// See [`crate::Builder::unstable_features`].
+    pub(crate) unstable_features: &'c [String],
 }
 
 impl<'c> RenderingContext<'c> {
@@ -51,6 +54,9 @@ impl<'c> RenderingContext<'c> {
                 Attribute::LinkSection(section) => Some(format!("#[link_section = \"{section}\"]")),
                 Attribute::NoMangle => Some("#[no_mangle]".to_string()),
                 Attribute::NonExhaustive => Some("#[non_exhaustive]".to_string()),
+                attr if crate::item_processor::is_doc_hidden_attr(attr) => {
+                    Some("#[doc(hidden)]".to_string())
+                }
                 Attribute::Repr(AttributeRepr {
                     kind,
                     align,
@@ -87,6 +93,13 @@ impl<'c> RenderingContext<'c> {
             })
             .collect();
 
+        if let Some(feature) = crate::item_processor::unstable_feature(item) {
+            annotations.push(format!("#[unstable(feature = {feature:?})]"));
+        }
+        if let Some(deprecation) = &item.deprecation {
+            annotations.push(render_deprecation(deprecation));
+        }
+
         annotations.sort_unstable();
         for annotation in annotations {
             tokens.push(Token::Annotation(annotation));
@@ -238,16 +251,20 @@ impl<'c> RenderingContext<'c> {
 
     /// Tuple fields are referenced by ID in JSON, but we need to look up the
     /// actual types that the IDs correspond to, in order to render the fields.
-    /// This helper does that for a slice of fields.
+    /// This helper does that for a slice of fields. Fields omitted with
+    /// `omit_doc_hidden` are resolved like stripped fields.
     fn resolve_tuple_fields(&self, fields: &[Option<Id>]) -> Vec<Option<&'c Type>> {
         let mut resolved_fields: Vec<Option<&Type>> = vec![];
 
         for id in fields {
             resolved_fields.push(
-                if let Some(Item {
-                    inner: ItemEnum::StructField(type_),
-                    ..
-                }) = id.as_ref().and_then(|id| self.crate_.index.get(id))
+                if let Some(
+                    item @ Item {
+                        inner: ItemEnum::StructField(type_),
+                        ..
+                    },
+                ) = id.as_ref().and_then(|id| self.crate_.index.get(id))
+                    && !(self.options.omit_doc_hidden && crate::item_processor::is_doc_hidden(item))
                 {
                     Some(type_)
                 } else {
@@ -371,7 +388,7 @@ impl<'c> RenderingContext<'c> {
         output
     }
 
-    fn render_type(&self, ty: &Type) -> Vec<Token> {
+    pub(crate) fn render_type(&self, ty: &Type) -> Vec<Token> {
         self.render_option_type(&Some(ty))
     }
 
@@ -558,7 +575,7 @@ impl<'c> RenderingContext<'c> {
         }
     }
 
-    fn render_resolved_path(&self, path: &Path) -> Vec<Token> {
+    pub(crate) fn render_resolved_path(&self, path: &Path) -> Vec<Token> {
         let mut output = vec![];
         if let Some(item) = self.best_item_for_id(&path.id) {
             output.extend(self.render_path(item.path()));
@@ -1092,3 +1109,20 @@ fn equals() -> Vec<Token> {
 fn arrow() -> Vec<Token> {
     vec![ws!(), Token::symbol("->"), ws!()]
 }
+
+/// Renders e.g. `#[deprecated(since = "1.2.0", note = "Use `bar` instead")]`.
+fn render_deprecation(deprecation: &Deprecation) -> String {
+    let mut args = vec![];
+    if let Some(since) = &deprecation.since {
+        args.push(format!("since = {since:?}"));
+    }
+    if let Some(note) = &deprecation.note {
+        args.push(format!("note = {note:?}"));
+    }
+
+    if args.is_empty() {
+        "#[deprecated]".to_owned()
+    } else {
+        format!("#[deprecated({})]", args.join(", "))
+    }
+}
@@ -0,0 +1,632 @@
+//! Classifies the entries of a [`PublicApiDiff`] according to
+//! [semver](https://doc.rust-lang.org/cargo/reference/semver.html). Each
+//! removed, changed, deprecated, moved and added item gets a [`SemverReason`], and each reason
+//! has a [`SemverImpact`]. The impact of the diff as a whole is given by
+//! [`SemverClassification::required_bump`].
+//!
+//! The classification is a heuristic based on what is visible in the public
+//! API. It can not detect every breaking change. For example, changing the
+//! body of a function is never detected.
+
+use std::collections::HashSet;
+
+use rustdoc_types::{Attribute, Item, ItemEnum, StructKind, VariantKind};
+
+use crate::{
+    PublicItem,
+    diff::{ChangedPublicItem, PublicApiDiff},
+    intermediate_public_item::IntermediatePublicItem,
+    tokens::Token,
+};
+
+/// How much a change to the public API affects downstream users, in semver
+/// terminology. Ordered from least to most severe, so the required bump of
+/// many changes is the [`max`](Ord::max) of their impacts.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SemverImpact {
+    /// Downstream code is not affected. A PATCH bump is sufficient.
+    Patch,
+
+    /// Functionality was added in a backwards compatible manner. Requires a
+    /// MINOR bump.
+    Minor,
+
+    /// Downstream code might break. Requires a MAJOR bump.
+    Major,
+}
+
+impl std::fmt::Display for SemverImpact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
+/// Why an entry in a [`PublicApiDiff`] has the [`SemverImpact`] it has.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum SemverReason {
+    /// An item was removed from the public API.
+    ItemRemoved,
+
+    /// An item was changed in a way that can break downstream code.
+    ItemChanged,
+
+    /// The rendered text of an item is unchanged, only how it is tokenized
+    /// differs.
+    RenderingChanged,
+
+    /// `#[non_exhaustive]` was removed from an item.
+    NonExhaustiveRemoved,
+
+    /// A function was made `const`.
+    FunctionMadeConst,
+
+    /// An item was added to the public API.
+    ItemAdded,
+
+    /// A variant was added to an enum that is not `#[non_exhaustive]`, which
+    /// breaks exhaustive `match`es.
+    VariantAddedToExhaustiveEnum,
+
+    /// A public field was added to a struct (or struct variant) whose fields
+    /// were all public and that is not `#[non_exhaustive]`, which breaks
+    /// struct literals and patterns.
+    FieldAddedToExhaustiveStruct,
+
+    /// An item without a default was added to a trait, which breaks
+    /// implementors of the trait.
+    RequiredTraitItemAdded,
+
+    /// An item with a default was added to a trait.
+    DefaultedTraitItemAdded,
+
+    /// An item was marked `#[deprecated]`. See
+    /// [`PublicApiDiff::deprecated`].
+    ItemDeprecated,
+
+    /// Only the `#[deprecated]` annotation of an item changed, e.g. its
+    /// `note`, or the item is no longer deprecated.
+    DeprecationChanged,
+
+    /// An item was moved to another path or renamed. See
+    /// [`PublicApiDiff::moved`].
+    ItemMoved,
+
+    /// A re-export of an item was removed, but the item itself is unchanged.
+    /// See [`PublicItem::reexport_paths`](crate::PublicItem::reexport_paths).
+    ReexportRemoved,
+
+    /// A re-export of an item was added, but the item itself is unchanged.
+    ReexportAdded,
+}
+
+impl SemverReason {
+    /// The [`SemverImpact`] of this reason.
+    #[must_use]
+    pub fn impact(self) -> SemverImpact {
+        match self {
+            Self::ItemRemoved
+            | Self::ItemChanged
+            | Self::VariantAddedToExhaustiveEnum
+            | Self::FieldAddedToExhaustiveStruct
+            | Self::RequiredTraitItemAdded
+            | Self::ItemMoved
+            | Self::ReexportRemoved => SemverImpact::Major,
+            Self::NonExhaustiveRemoved
+            | Self::FunctionMadeConst
+            | Self::ItemAdded
+            | Self::DefaultedTraitItemAdded
+            | Self::ItemDeprecated
+            | Self::ReexportAdded => SemverImpact::Minor,
+            Self::RenderingChanged | Self::DeprecationChanged => SemverImpact::Patch,
+        }
+    }
+}
+
+impl std::fmt::Display for SemverReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ItemRemoved => "item removed",
+            Self::ItemChanged => "item changed",
+            Self::RenderingChanged => "only rendering changed",
+            Self::NonExhaustiveRemoved => "#[non_exhaustive] removed",
+            Self::FunctionMadeConst => "function made const",
+            Self::ItemAdded => "item added",
+            Self::VariantAddedToExhaustiveEnum => "variant added to exhaustive enum",
+            Self::FieldAddedToExhaustiveStruct => "field added to exhaustive struct",
+            Self::RequiredTraitItemAdded => "required item added to trait",
+            Self::DefaultedTraitItemAdded => "defaulted item added to trait",
+            Self::ItemDeprecated => "item deprecated",
+            Self::DeprecationChanged => "only deprecation changed",
+            Self::ItemMoved => "item moved or renamed",
+            Self::ReexportRemoved => "re-export removed",
+            Self::ReexportAdded => "re-export added",
+        })
+    }
+}
+
+/// An entry in a [`PublicApiDiff`] together with the reason for its
+/// [`SemverImpact`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Classified<'a, T> {
+    /// The entry in the [`PublicApiDiff`].
+    pub entry: &'a T,
+
+    /// Why the entry has the impact it has.
+    pub reason: SemverReason,
+}
+
+impl<T> Classified<'_, T> {
+    /// Shorthand for `self.reason.impact()`.
+    #[must_use]
+    pub fn impact(&self) -> SemverImpact {
+        self.reason.impact()
+    }
+}
+
+/// The return value of [`PublicApiDiff::classify`]. Has the same layout and
+/// order as the [`PublicApiDiff`] it classifies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemverClassification<'a> {
+    /// Classified [`PublicApiDiff::removed`] items.
+    pub removed: Vec<Classified<'a, PublicItem>>,
+
+    /// Classified [`PublicApiDiff::changed`] items.
+    pub changed: Vec<Classified<'a, ChangedPublicItem>>,
+
+    /// Classified [`PublicApiDiff::deprecated`] items.
+    pub deprecated: Vec<Classified<'a, ChangedPublicItem>>,
+
+    /// Classified [`PublicApiDiff::moved`] items.
+    pub moved: Vec<Classified<'a, ChangedPublicItem>>,
+
+    /// Classified [`PublicApiDiff::added`] items.
+    pub added: Vec<Classified<'a, PublicItem>>,
+}
+
+impl SemverClassification<'_> {
+    /// The smallest version bump that covers all entries. If the diff is
+    /// empty, [`SemverImpact::Patch`] is returned.
+    #[must_use]
+    pub fn required_bump(&self) -> SemverImpact {
+        let removed = self.removed.iter().map(Classified::impact);
+        let changed = self.changed.iter().map(Classified::impact);
+        let deprecated = self.deprecated.iter().map(Classified::impact);
+        let moved = self.moved.iter().map(Classified::impact);
+        let added = self.added.iter().map(Classified::impact);
+        removed
+            .chain(changed)
+            .chain(deprecated)
+            .chain(moved)
+            .chain(added)
+            .max()
+            .unwrap_or(SemverImpact::Patch)
+    }
+}
+
+pub(crate) fn classify(diff: &PublicApiDiff) -> SemverClassification<'_> {
+    // If a parent item was added too (e.g. an entire enum), adding its children
+    // can't break anything, since there is no downstream code using the parent
+    let added_paths: HashSet<&[String]> = diff
+        .added
+        .iter()
+        .map(|a| a.sortable_path.as_slice())
+        .collect();
+    let parent_added = |item: &PublicItem| {
+        item.sortable_path
+            .split_last()
+            .is_some_and(|(_, parent_path)| added_paths.contains(parent_path))
+    };
+
+    SemverClassification {
+        removed: diff
+            .removed
+            .iter()
+            .map(|entry| Classified {
+                entry,
+                reason: SemverReason::ItemRemoved,
+            })
+            .collect(),
+        changed: diff
+            .changed
+            .iter()
+            .map(|entry| Classified {
+                entry,
+                reason: classify_changed(entry),
+            })
+            .collect(),
+        deprecated: diff
+            .deprecated
+            .iter()
+            .map(|entry| Classified {
+                entry,
+                reason: SemverReason::ItemDeprecated,
+            })
+            .collect(),
+        moved: diff
+            .moved
+            .iter()
+            .map(|entry| Classified {
+                entry,
+                reason: SemverReason::ItemMoved,
+            })
+            .collect(),
+        added: diff
+            .added
+            .iter()
+            .map(|entry| Classified {
+                entry,
+                reason: if parent_added(entry) {
+                    SemverReason::ItemAdded
+                } else {
+                    entry.reason_if_added
+                },
+            })
+            .collect(),
+    }
+}
+
+fn classify_changed(changed: &ChangedPublicItem) -> SemverReason {
+    let old = &changed.old.tokens;
+    let new = &changed.new.tokens;
+
+    if old == new {
+        let new_paths = changed.new.reexport_paths();
+        if changed
+            .old
+            .reexport_paths()
+            .iter()
+            .any(|path| !new_paths.contains(path))
+        {
+            SemverReason::ReexportRemoved
+        } else {
+            SemverReason::ReexportAdded
+        }
+    } else if changed.old.to_string() == changed.new.to_string() {
+        SemverReason::RenderingChanged
+    } else if without_non_exhaustive(old).as_slice() == new.as_slice() {
+        SemverReason::NonExhaustiveRemoved
+    } else if old.as_slice() == without_const_qualifier(new).as_slice() {
+        SemverReason::FunctionMadeConst
+    } else if without_deprecation(old) == without_deprecation(new) {
+        SemverReason::DeprecationChanged
+    } else {
+        SemverReason::ItemChanged
+    }
+}
+
+fn without_non_exhaustive(tokens: &[Token]) -> Vec<Token> {
+    let annotation = Token::Annotation("#[non_exhaustive]".to_string());
+    without_token_and_whitespace(tokens, &annotation)
+}
+
+/// If `token` is a rendered `#[deprecated]` annotation, with or without
+/// `since` and `note`.
+pub(crate) fn is_deprecation(token: &Token) -> bool {
+    matches!(token, Token::Annotation(annotation) if annotation.starts_with("#[deprecated"))
+}
+
+pub(crate) fn without_deprecation(tokens: &[Token]) -> Vec<Token> {
+    match tokens.iter().find(|t| is_deprecation(t)) {
+        Some(annotation) => without_token_and_whitespace(tokens, annotation),
+        None => tokens.to_vec(),
+    }
+}
+
+fn without_const_qualifier(tokens: &[Token]) -> Vec<Token> {
+    if tokens.iter().any(|t| *t == Token::kind("fn")) {
+        without_token_and_whitespace(tokens, &Token::qualifier("const"))
+    } else {
+        tokens.to_vec()
+    }
+}
+
+/// Removes the first occurrence of `token` as well as the whitespace that
+/// follows it.
+fn without_token_and_whitespace(tokens: &[Token], token: &Token) -> Vec<Token> {
+    let mut output = tokens.to_vec();
+    if let Some(index) = output.iter().position(|t| t == token) {
+        output.remove(index);
+        if output.get(index) == Some(&Token::Whitespace) {
+            output.remove(index);
+        }
+    }
+    output
+}
+
+/// What it would mean for downstream code if `public_item` was added to the
+/// public API. We must figure this out when the item is created, since it
+/// depends on the parent item, which is not available when diffing.
+pub(crate) fn reason_if_added(public_item: &IntermediatePublicItem<'_>) -> SemverReason {
+    let path = public_item.path();
+    let Some(parent) = path.len().checked_sub(2).map(|i| path[i].item.item) else {
+        return SemverReason::ItemAdded;
+    };
+
+    match (&public_item.item().inner, &parent.inner) {
+        (ItemEnum::Variant(_), ItemEnum::Enum(_)) if !is_non_exhaustive(parent) => {
+            SemverReason::VariantAddedToExhaustiveEnum
+        }
+        (ItemEnum::StructField(_), _) if is_exhaustive_struct(parent) => {
+            SemverReason::FieldAddedToExhaustiveStruct
+        }
+        (ItemEnum::Function(function), ItemEnum::Trait(_)) => trait_item_reason(function.has_body),
+        (ItemEnum::AssocConst { value, .. }, ItemEnum::Trait(_)) => {
+            trait_item_reason(value.is_some())
+        }
+        (ItemEnum::AssocType { type_, .. }, ItemEnum::Trait(_)) => {
+            trait_item_reason(type_.is_some())
+        }
+        _ => SemverReason::ItemAdded,
+    }
+}
+
+fn trait_item_reason(has_default: bool) -> SemverReason {
+    if has_default {
+        SemverReason::DefaultedTraitItemAdded
+    } else {
+        SemverReason::RequiredTraitItemAdded
+    }
+}
+
+fn is_non_exhaustive(item: &Item) -> bool {
+    item.attrs.contains(&Attribute::NonExhaustive)
+}
+
+/// If all fields of a struct (or struct variant) are public and it is not
+/// `#[non_exhaustive]`, downstream code can construct and destructure it
+/// with a struct literal.
+fn is_exhaustive_struct(item: &Item) -> bool {
+    let has_stripped_fields = match &item.inner {
+        ItemEnum::Struct(rustdoc_types::Struct {
+            kind:
+                StructKind::Plain {
+                    has_stripped_fields,
+                    ..
+                },
+            ..
+        })
+        | ItemEnum::Variant(rustdoc_types::Variant {
+            kind:
+                VariantKind::Struct {
+                    has_stripped_fields,
+                    ..
+                },
+            ..
+        }) => *has_stripped_fields,
+        _ => return false,
+    };
+
+    !has_stripped_fields && !is_non_exhaustive(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustdoc_types::Id;
+
+    use super::*;
+    use crate::PublicApi;
+
+    #[test]
+    fn empty_diff_requires_patch() {
+        let diff = PublicApiDiff::between(api([]), api([]));
+        assert_eq!(diff.classify().required_bump(), SemverImpact::Patch);
+    }
+
+    #[test]
+    fn removed_item_is_major() {
+        let diff = PublicApiDiff::between(api([fn_item(&["a", "f"], false)]), api([]));
+        let classification = diff.classify();
+        assert_eq!(classification.removed[0].reason, SemverReason::ItemRemoved);
+        assert_eq!(classification.required_bump(), SemverImpact::Major);
+    }
+
+    #[test]
+    fn added_item_is_minor() {
+        let diff = PublicApiDiff::between(api([]), api([fn_item(&["a", "f"], false)]));
+        let classification = diff.classify();
+        assert_eq!(classification.added[0].reason, SemverReason::ItemAdded);
+        assert_eq!(classification.required_bump(), SemverImpact::Minor);
+    }
+
+    #[test]
+    fn added_variant_to_exhaustive_enum_is_major() {
+        let mut variant = item(&["a", "E", "V"], vec![Token::identifier("a::E::V")]);
+        variant.reason_if_added = SemverReason::VariantAddedToExhaustiveEnum;
+
+        let diff = PublicApiDiff::between(api([]), api([variant]));
+        let classification = diff.classify();
+        assert_eq!(
+            classification.added[0].reason,
+            SemverReason::VariantAddedToExhaustiveEnum
+        );
+        assert_eq!(classification.required_bump(), SemverImpact::Major);
+    }
+
+    #[test]
+    fn children_of_added_parent_are_minor() {
+        let enum_ = item(&["a", "E"], vec![Token::identifier("a::E")]);
+        let mut variant = item(&["a", "E", "V"], vec![Token::identifier("a::E::V")]);
+        variant.reason_if_added = SemverReason::VariantAddedToExhaustiveEnum;
+
+        let diff = PublicApiDiff::between(api([]), api([enum_, variant]));
+        let classification = diff.classify();
+        assert!(
+            classification
+                .added
+                .iter()
+                .all(|c| c.reason == SemverReason::ItemAdded)
+        );
+        assert_eq!(classification.required_bump(), SemverImpact::Minor);
+    }
+
+    #[test]
+    fn changed_signature_is_major() {
+        let old = item(&["a", "f"], vec![Token::function("f"), Token::symbol("()")]);
+        let new = item(
+            &["a", "f"],
+            vec![Token::function("f"), Token::symbol("(u8)")],
+        );
+
+        let diff = PublicApiDiff::between(api([old]), api([new]));
+        assert_eq!(diff.classify().changed[0].reason, SemverReason::ItemChanged);
+    }
+
+    #[test]
+    fn made_const_is_minor() {
+        let diff = PublicApiDiff::between(
+            api([fn_item(&["a", "f"], false)]),
+            api([fn_item(&["a", "f"], true)]),
+        );
+        assert_eq!(
+            diff.classify().changed[0].reason,
+            SemverReason::FunctionMadeConst
+        );
+
+        let diff = PublicApiDiff::between(
+            api([fn_item(&["a", "f"], true)]),
+            api([fn_item(&["a", "f"], false)]),
+        );
+        assert_eq!(diff.classify().changed[0].reason, SemverReason::ItemChanged);
+    }
+
+    #[test]
+    fn removed_non_exhaustive_is_minor() {
+        let exhaustive = vec![Token::kind("enum"), Token::Whitespace, Token::type_("E")];
+        let mut non_exhaustive = vec![
+            Token::Annotation("#[non_exhaustive]".to_string()),
+            Token::Whitespace,
+        ];
+        non_exhaustive.extend(exhaustive.clone());
+
+        let diff = PublicApiDiff::between(
+            api([item(&["a", "E"], non_exhaustive.clone())]),
+            api([item(&["a", "E"], exhaustive.clone())]),
+        );
+        assert_eq!(
+            diff.classify().changed[0].reason,
+            SemverReason::NonExhaustiveRemoved
+        );
+
+        let diff = PublicApiDiff::between(
+            api([item(&["a", "E"], exhaustive)]),
+            api([item(&["a", "E"], non_exhaustive)]),
+        );
+        assert_eq!(diff.classify().changed[0].reason, SemverReason::ItemChanged);
+    }
+
+    #[test]
+    fn same_text_different_tokens_is_patch() {
+        let old = item(&["a", "T"], vec![Token::identifier("T")]);
+        let new = item(&["a", "T"], vec![Token::type_("T")]);
+
+        let diff = PublicApiDiff::between(api([old]), api([new]));
+        let classification = diff.classify();
+        assert_eq!(
+            classification.changed[0].reason,
+            SemverReason::RenderingChanged
+        );
+        assert_eq!(classification.required_bump(), SemverImpact::Patch);
+    }
+
+    #[test]
+    fn deprecated_is_minor() {
+        let diff = PublicApiDiff::between(
+            api([fn_item(&["a", "f"], false)]),
+            api([deprecated(fn_item(&["a", "f"], false), "#[deprecated]")]),
+        );
+        let classification = diff.classify();
+        assert_eq!(
+            classification.deprecated[0].reason,
+            SemverReason::ItemDeprecated
+        );
+        assert_eq!(classification.required_bump(), SemverImpact::Minor);
+    }
+
+    #[test]
+    fn changed_deprecation_is_patch() {
+        let diff = PublicApiDiff::between(
+            api([deprecated(fn_item(&["a", "f"], false), "#[deprecated]")]),
+            api([deprecated(
+                fn_item(&["a", "f"], false),
+                "#[deprecated(note = \"Use g\")]",
+            )]),
+        );
+        let classification = diff.classify();
+        assert_eq!(
+            classification.changed[0].reason,
+            SemverReason::DeprecationChanged
+        );
+        assert_eq!(classification.required_bump(), SemverImpact::Patch);
+    }
+
+    #[test]
+    fn moved_is_major() {
+        let fn_at = |path: &[&str]| {
+            let mut tokens = vec![Token::kind("fn"), Token::Whitespace];
+            for module in &path[..path.len() - 1] {
+                tokens.extend([Token::identifier(*module), Token::symbol("::")]);
+            }
+            tokens.extend([Token::function(path[path.len() - 1]), Token::symbol("()")]);
+            item(path, tokens)
+        };
+
+        let diff =
+            PublicApiDiff::between(api([fn_at(&["a", "f"])]), api([fn_at(&["a", "b", "f"])]));
+        let classification = diff.classify();
+        assert_eq!(classification.moved[0].reason, SemverReason::ItemMoved);
+        assert_eq!(classification.required_bump(), SemverImpact::Major);
+    }
+
+    fn deprecated(mut item: PublicItem, annotation: &str) -> PublicItem {
+        item.tokens.splice(
+            0..0,
+            [Token::Annotation(annotation.to_string()), Token::Whitespace],
+        );
+        item
+    }
+
+    fn fn_item(path: &[&str], is_const: bool) -> PublicItem {
+        let mut tokens = vec![Token::qualifier("pub"), Token::Whitespace];
+        if is_const {
+            tokens.extend([Token::qualifier("const"), Token::Whitespace]);
+        }
+        tokens.extend([
+            Token::kind("fn"),
+            Token::Whitespace,
+            Token::function(path.join("::")),
+            Token::symbol("()"),
+        ]);
+        item(path, tokens)
+    }
+
+    fn item(path: &[&str], tokens: Vec<Token>) -> PublicItem {
+        PublicItem {
+            sortable_path: path.iter().map(ToString::to_string).collect(),
+            tokens,
+            parent_id: None,
+            id: Id(1234),
+            reason_if_added: SemverReason::ItemAdded,
+            doc_hidden: false,
+            unstable: false,
+            kind: None,
+            path: vec![],
+            canonical_path: String::new(),
+            reexport: false,
+            reexport_paths: vec![],
+        }
+    }
+
+    fn api(items: impl IntoIterator<Item = PublicItem>) -> PublicApi {
+        PublicApi {
+            items: items.into_iter().collect(),
+            missing_item_ids: vec![],
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! Turns a text snapshot of a public API, i.e. the output of
+//! [`PublicApi`]'s `Display` impl, back into a [`PublicApi`]. The text does not
+//! say what kind of Rust item each token is, so the tokens are a best-effort
+//! guess. Since the guess only depends on the text, two items with the same
+//! text always get the same tokens, which is what matters when diffing.
+
+use rustdoc_types::Id;
+
+use crate::{
+    PublicApi, PublicItem, public_item::PublicItemPath, semver::SemverReason, tokens::Token,
+};
+
+/// Text snapshots do not contain rustdoc JSON IDs.
+const NO_ID: Id = Id(0);
+
+pub(crate) fn public_api_from_text_snapshot(snapshot: &str) -> PublicApi {
+    let mut items = vec![];
+
+    // Items of an `impl` are listed right after it, so we can nest them under
+    // the `impl` like when building from rustdoc JSON. This makes the diff
+    // tell apart e.g. methods with the same name in different `impl`s.
+    let mut current_impl: Option<PublicItemPath> = None;
+    for line in snapshot.lines().filter(|line| !line.trim().is_empty()) {
+        let mut item = public_item_from_line(line);
+        if is_impl(&item.tokens) {
+            current_impl = Some(item.sortable_path.clone());
+        } else if let Some(impl_path) = &current_impl
+            && let Some((name, type_path)) = item.sortable_path.split_last()
+            && impl_path.split_last().is_some_and(|(_, p)| p == type_path)
+        {
+            let mut path = impl_path.clone();
+            path.push(name.clone());
+            item.sortable_path = path;
+        } else {
+            current_impl = None;
+        }
+        items.push(item);
+    }
+    items.sort_by(PublicItem::grouping_cmp);
+
+    PublicApi {
+        items,
+        missing_item_ids: vec![],
+    }
+}
+
+fn public_item_from_line(line: &str) -> PublicItem {
+    let (line, reexport_paths) = match line.split_once(" // re-exported at ") {
+        Some((line, paths)) => (line, paths.split(", ").map(String::from).collect()),
+        None => (line, vec![]),
+    };
+    let tokens = tokenize(line);
+    // Items inside a `#[doc(hidden)]` or `#[unstable]` item are not annotated,
+    // so this only finds the annotated items themselves
+    let doc_hidden = tokens.contains(&Token::Annotation("#[doc(hidden)]".to_owned()));
+    let unstable = tokens
+        .iter()
+        .any(|t| matches!(t, Token::Annotation(a) if a.starts_with("#[unstable(")));
+    let mut item = PublicItem {
+        sortable_path: sortable_path(line, &tokens),
+        tokens,
+        parent_id: None,
+        id: NO_ID,
+        reason_if_added: SemverReason::ItemAdded,
+        doc_hidden,
+        unstable,
+        kind: None,
+        path: vec![],
+        canonical_path: String::new(),
+        reexport: false,
+        reexport_paths,
+    };
+    // Whether an item is a re-export alias is not part of the rendered line,
+    // so all items are taken to be at their canonical path
+    if let Some(path) = item.rendered_path() {
+        let names: Vec<String> = path.names.iter().map(|name| (*name).to_owned()).collect();
+        item.canonical_path = names.join("::");
+        item.path = names;
+    }
+    item
+}
+
+/// Splits a line into tokens such that the tokens together render the exact
+/// same line.
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let at_start = tokens
+            .iter()
+            .all(|t| matches!(t, Token::Whitespace | Token::Annotation(_)));
+
+        let len = if c == ' ' {
+            tokens.push(Token::Whitespace);
+            1
+        } else if at_start && rest.starts_with("#[") {
+            let len = annotation_len(rest);
+            tokens.push(Token::Annotation(rest[..len].to_owned()));
+            len
+        } else if c == '\'' && rest[1..].starts_with(is_word_char) {
+            let len = 1 + word_len(&rest[1..]);
+            tokens.push(Token::lifetime(&rest[..len]));
+            len
+        } else if is_word_char(c) {
+            let len = word_len(rest);
+            tokens.push(word_token(&rest[..len]));
+            len
+        } else if rest.starts_with("::") || rest.starts_with("->") {
+            tokens.push(Token::symbol(&rest[..2]));
+            2
+        } else {
+            tokens.push(Token::symbol(c));
+            c.len_utf8()
+        };
+
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+fn word_token(word: &str) -> Token {
+    match word {
+        "pub" | "const" | "async" | "unsafe" | "extern" => Token::qualifier(word),
+        "mod" | "struct" | "enum" | "union" | "fn" | "trait" | "type" | "static" | "macro"
+        | "use" | "crate" | "proc" | "alias" => Token::kind(word),
+        "impl" | "for" | "where" | "dyn" | "as" | "mut" => Token::keyword(word),
+        "self" | "Self" => Token::self_(word),
+        "bool" | "char" | "str" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8"
+        | "i16" | "i32" | "i64" | "i128" | "isize" | "f16" | "f32" | "f64" | "f128" => {
+            Token::primitive(word)
+        }
+        _ => Token::identifier(word),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn word_len(s: &str) -> usize {
+    s.find(|c| !is_word_char(c)).unwrap_or(s.len())
+}
+
+/// The length of e.g. `#[repr(C)]`, taking nested brackets into account.
+fn annotation_len(s: &str) -> usize {
+    let mut depth = 0;
+    for (index, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Without rustdoc JSON we do not know the sorting prefix of items, so we use
+/// the path as written instead. To keep `impl`s together with the type they
+/// are for, the path of an `impl` is the path of its type followed by the
+/// whole line. Items without a path found are grouped by the whole line.
+fn sortable_path(line: &str, tokens: &[Token]) -> PublicItemPath {
+    let rest = skip_declaration(tokens);
+    let mut path = if is_impl(tokens) {
+        let mut path = path(impl_self_type(&rest[1..]));
+        path.push(line.to_owned());
+        path
+    } else {
+        path(rest)
+    };
+
+    if path.is_empty() {
+        path.push(line.to_owned());
+    }
+    path
+}
+
+fn is_impl(tokens: &[Token]) -> bool {
+    skip_declaration(tokens).first() == Some(&Token::keyword("impl"))
+}
+
+/// Skips annotations, qualifiers and the kind of the item, e.g. `pub fn`.
+fn skip_declaration(mut tokens: &[Token]) -> &[Token] {
+    while let Some((
+        Token::Annotation(_) | Token::Qualifier(_) | Token::Kind(_) | Token::Whitespace,
+        tail,
+    )) = tokens.split_first()
+    {
+        tokens = tail;
+    }
+    tokens
+}
+
+/// Given the tokens after `impl`, returns the tokens starting at the type
+/// that the `impl` is for.
+fn impl_self_type(tokens: &[Token]) -> &[Token] {
+    let tokens = skip_whitespace(skip_generic_args(tokens));
+
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol(s) if s == "<" => depth += 1,
+            Token::Symbol(s) if s == ">" => depth -= 1,
+            Token::Keyword(k) if depth == 0 && k == "for" => {
+                return skip_whitespace(&tokens[index + 1..]);
+            }
+            Token::Keyword(k) if depth == 0 && k == "where" => break,
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Returns e.g. `["a", "B", "c"]` for tokens starting with `a::B<T>::c`.
+fn path(mut tokens: &[Token]) -> PublicItemPath {
+    let mut path = vec![];
+    while let Some((
+        Token::Identifier(segment) | Token::Self_(segment) | Token::Primitive(segment),
+        tail,
+    )) = tokens.split_first()
+    {
+        path.push(segment.clone());
+        tokens = skip_generic_args(tail);
+
+        match tokens.split_first() {
+            Some((Token::Symbol(separator), tail)) if separator == "::" => tokens = tail,
+            _ => break,
+        }
+    }
+    path
+}
+
+fn skip_whitespace(tokens: &[Token]) -> &[Token] {
+    let start = tokens
+        .iter()
+        .position(|t| *t != Token::Whitespace)
+        .unwrap_or(tokens.len());
+    &tokens[start..]
+}
+
+/// Skips `<...>` if `tokens` starts with it.
+fn skip_generic_args(tokens: &[Token]) -> &[Token] {
+    if tokens.first() != Some(&Token::symbol("<")) {
+        return tokens;
+    }
+
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol(s) if s == "<" => depth += 1,
+            Token::Symbol(s) if s == ">" => {
+                depth -= 1;
+                if depth == 0 {
+                    return &tokens[index + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    &[]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_same_text() {
+        for line in [
+            "#[repr(C)] #[non_exhaustive] pub struct a::B<'a, T: core::fmt::Debug>",
+            "pub fn a::B<'a, T>::f(&self, core::option::Option<alloc::vec::Vec<T>>) -> &'a str",
+            "impl<T> core::convert::From<T> for a::B<'_, T> where T: core::marker::Send",
+            "pub a::Enum::Variant(µ, ())",
+        ] {
+            assert_eq!(public_item_from_line(line).to_string(), line);
+        }
+    }
+
+    #[test]
+    fn reexport_note() {
+        let item = public_item_from_line("pub struct a::B // re-exported at a::C, d::B");
+        assert_eq!(item.to_string(), "pub struct a::B");
+        assert_eq!(item.reexport_paths(), ["a::C", "d::B"]);
+    }
+
+    #[test]
+    fn path_after_fn_bound() {
+        let item =
+            public_item_from_line("pub fn a::B<F: core::ops::function::Fn() -> T>::f(&self)");
+        assert_eq!(item.path(), ["a", "B", "f"]);
+    }
+
+    #[test]
+    fn sortable_paths() {
+        for (line, expected) in [
+            ("pub mod a", vec!["a"]),
+            ("#[non_exhaustive] pub struct a::B<T>", vec!["a", "B"]),
+            ("pub a::B::field: usize", vec!["a", "B", "field"]),
+            (
+                "pub const unsafe fn a::B<T>::f(&self) -> Self",
+                vec!["a", "B", "f"],
+            ),
+            (
+                "impl<T: a::Trait> a::Trait for a::B<T>",
+                vec!["a", "B", "impl<T: a::Trait> a::Trait for a::B<T>"],
+            ),
+            ("impl a::B", vec!["a", "B", "impl a::B"]),
+            ("impl<T> a::Trait for &T", vec!["impl<T> a::Trait for &T"]),
+        ] {
+            assert_eq!(
+                public_item_from_line(line).sortable_path,
+                expected,
+                "{line}"
+            );
+        }
+    }
+}
@@ -4,6 +4,11 @@ use crate::public_item::PublicItem;
 
 /// A token in a rendered [`PublicItem`], used to apply syntax coloring in downstream applications.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "text", rename_all = "snake_case")
+)]
 pub enum Token {
     /// A symbol, like `=` or `::<`
     Symbol(String),
@@ -18,6 +23,7 @@ pub enum Token {
     /// An annotation, used e.g. for Rust attributes.
     Annotation(String),
     /// The identifier self, the text can be `self` or `Self`
+    #[cfg_attr(feature = "serde", serde(rename = "self"))]
     Self_(String),
     /// The identifier for a function, like `fn_arg` in `comprehensive_api::functions::fn_arg`
     Function(String),
@@ -95,6 +95,511 @@ fn omit_auto_trait_impls() {
     );
 }
 
+#[test]
+fn only_and_exclude_paths() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    assert_public_api(
+        simplified_builder_for_crate("../test-apis/example_api-v0.2.0", &build_dir)
+            .only_paths(["example_api::Struct*::**"])
+            .exclude_paths(["example_api::StructV2", "**::v2_*"]),
+        "example_api-v0.2.0-only_and_exclude_paths",
+    );
+}
+
+#[test]
+fn doc_hidden_items() {
+    let root = temp_lib(
+        r#"
+#[doc(hidden)]
+pub mod __private {
+    pub fn helper() {}
+}
+pub struct Visible {
+    #[doc(hidden)]
+    pub hidden_field: u8,
+}
+impl Visible {
+    #[doc(hidden)]
+    pub fn hidden_method(&self) {}
+    pub fn method(&self) {}
+}
+    "#,
+    );
+    let json = rustdoc_json::Builder::default()
+        .manifest_path(root.path().join("Cargo.toml"))
+        .toolchain("nightly")
+        .target_dir(root.path())
+        .document_hidden_items(true)
+        .quiet(true)
+        .build()
+        .unwrap();
+
+    let api = public_api::Builder::from_rustdoc_json(&json)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .build()
+        .unwrap();
+    let hidden: Vec<String> = api
+        .items()
+        .filter(|item| item.is_doc_hidden())
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        hidden,
+        vec![
+            "#[doc(hidden)] pub mod lib::__private",
+            "pub fn lib::__private::helper()",
+            "#[doc(hidden)] pub lib::Visible::hidden_field: u8",
+            "#[doc(hidden)] pub fn lib::Visible::hidden_method(&self)",
+        ]
+    );
+
+    let api = public_api::Builder::from_rustdoc_json(&json)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .omit_doc_hidden(true)
+        .build()
+        .unwrap();
+    assert_eq!(
+        api.to_string(),
+        "\
+pub mod lib
+pub struct lib::Visible
+impl lib::Visible
+pub fn lib::Visible::method(&self)
+"
+    );
+}
+
+#[test]
+fn newly_deprecated_items() {
+    let old = rustdoc_json_for_lib(
+        r#"
+pub fn deprecated() {}
+pub fn deprecated_and_changed() {}
+pub fn unchanged() {}
+    "#,
+    );
+    let new = rustdoc_json_for_lib(
+        r#"
+#[deprecated(since = "0.2.0", note = "Use `unchanged` instead")]
+pub fn deprecated() {}
+#[deprecated]
+pub fn deprecated_and_changed(_: u8) {}
+pub fn unchanged() {}
+    "#,
+    );
+
+    let old = public_api::Builder::from_rustdoc_json(old.json_path)
+        .build()
+        .unwrap();
+    let new = public_api::Builder::from_rustdoc_json(new.json_path)
+        .build()
+        .unwrap();
+    let diff = public_api::diff::PublicApiDiff::between(old, new);
+
+    let changed: Vec<String> = diff.changed.iter().map(|c| c.new.to_string()).collect();
+    let deprecated: Vec<String> = diff.deprecated.iter().map(|c| c.new.to_string()).collect();
+    assert_eq!(
+        changed,
+        vec!["#[deprecated] pub fn lib::deprecated_and_changed(u8)"]
+    );
+    assert_eq!(
+        deprecated,
+        vec![
+            r#"#[deprecated(since = "0.2.0", note = "Use `unchanged` instead")] pub fn lib::deprecated()"#
+        ]
+    );
+    assert!(diff.deprecated[0].new.is_deprecated());
+    assert!(!diff.deprecated[0].old.is_deprecated());
+}
+
+#[test]
+fn moved_and_renamed_items() {
+    let old = rustdoc_json_for_lib(
+        r#"
+pub struct Moved {
+    pub field: u32,
+}
+impl Moved {
+    pub fn new() -> Self {
+        Self { field: 0 }
+    }
+}
+pub struct OldName {
+    pub value: u8,
+}
+pub fn old_fn(_: u8) {}
+pub fn removed() {}
+    "#,
+    );
+    let new = rustdoc_json_for_lib(
+        r#"
+pub mod inner {
+    pub struct Moved {
+        pub field: u32,
+    }
+    impl Moved {
+        pub fn new() -> Self {
+            Self { field: 0 }
+        }
+    }
+}
+pub struct NewName {
+    pub value: u8,
+}
+pub fn new_fn(_: u8) {}
+    "#,
+    );
+
+    let old = public_api::Builder::from_rustdoc_json(old.json_path)
+        .omit_blanket_impls(true)
+        .build()
+        .unwrap();
+    let new = public_api::Builder::from_rustdoc_json(new.json_path)
+        .omit_blanket_impls(true)
+        .build()
+        .unwrap();
+    let diff = public_api::diff::PublicApiDiff::between(old, new);
+
+    let moved: Vec<String> = diff
+        .moved
+        .iter()
+        .map(|m| format!("{} -> {}", m.old, m.new))
+        .collect();
+    let removed: Vec<String> = diff.removed.iter().map(ToString::to_string).collect();
+    let added: Vec<String> = diff.added.iter().map(ToString::to_string).collect();
+    // Renamed functions are not paired, since they have no children that
+    // match too
+    assert_eq!(
+        removed,
+        vec!["pub fn lib::old_fn(u8)", "pub fn lib::removed()"]
+    );
+    assert_eq!(added, vec!["pub mod lib::inner", "pub fn lib::new_fn(u8)"]);
+    assert!(moved.contains(&"pub struct lib::Moved -> pub struct lib::inner::Moved".to_owned()));
+    assert!(moved.contains(
+        &"pub fn lib::Moved::new() -> Self -> pub fn lib::inner::Moved::new() -> Self".to_owned()
+    ));
+    assert!(moved.contains(&"pub struct lib::OldName -> pub struct lib::NewName".to_owned()));
+    assert!(
+        moved.contains(&"pub lib::OldName::value: u8 -> pub lib::NewName::value: u8".to_owned())
+    );
+    assert!(moved.contains(
+        &"impl core::marker::Send for lib::Moved -> impl core::marker::Send for lib::inner::Moved"
+            .to_owned()
+    ));
+}
+
+#[test]
+fn reexport_aliases() {
+    let lib = r#"
+pub mod c {
+    pub struct B {
+        pub field: u32,
+    }
+}
+pub mod a {
+    pub use crate::c::B;
+}
+mod private {
+    pub fn only_reexported() {}
+}
+pub use private::only_reexported;
+pub use c::B as Renamed;
+    "#;
+
+    let json = rustdoc_json_for_lib(lib);
+    let api = public_api::Builder::from_rustdoc_json(&json.json_path)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .build()
+        .unwrap();
+    let paths: Vec<(String, &str, bool)> = api
+        .items()
+        .filter(|item| !item.to_string().starts_with("impl"))
+        .map(|item| (item.to_string(), item.canonical_path(), item.is_reexport()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("pub mod lib".to_owned(), "lib", false),
+            ("pub mod lib::a".to_owned(), "lib::a", false),
+            ("pub struct lib::a::B".to_owned(), "lib::c::B", true),
+            (
+                "pub lib::a::B::field: u32".to_owned(),
+                "lib::c::B::field",
+                true
+            ),
+            ("pub mod lib::c".to_owned(), "lib::c", false),
+            ("pub struct lib::c::B".to_owned(), "lib::c::B", false),
+            (
+                "pub lib::c::B::field: u32".to_owned(),
+                "lib::c::B::field",
+                false
+            ),
+            ("pub struct lib::Renamed".to_owned(), "lib::c::B", true),
+            (
+                "pub lib::Renamed::field: u32".to_owned(),
+                "lib::c::B::field",
+                true
+            ),
+            (
+                "pub fn lib::only_reexported()".to_owned(),
+                "lib::only_reexported",
+                false
+            ),
+        ]
+    );
+
+    let collapsed = public_api::Builder::from_rustdoc_json(&json.json_path)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .collapse_reexports(true)
+        .build()
+        .unwrap();
+    let items: Vec<(String, &[String])> = collapsed
+        .items()
+        .filter(|item| !item.to_string().starts_with("impl"))
+        .map(|item| (item.to_string(), item.reexport_paths()))
+        .collect();
+    let aliases = ["lib::Renamed".to_owned(), "lib::a::B".to_owned()];
+    assert_eq!(
+        items,
+        vec![
+            ("pub mod lib".to_owned(), &[][..]),
+            ("pub mod lib::a".to_owned(), &[]),
+            ("pub mod lib::c".to_owned(), &[]),
+            ("pub struct lib::c::B".to_owned(), &aliases[..]),
+            ("pub lib::c::B::field: u32".to_owned(), &[]),
+            ("pub fn lib::only_reexported()".to_owned(), &[]),
+        ]
+    );
+    assert!(
+        collapsed
+            .to_string()
+            .contains("pub struct lib::c::B // re-exported at lib::Renamed, lib::a::B\n")
+    );
+}
+
+#[test]
+fn apply_filters_like_build() {
+    let lib = r#"
+pub mod c {
+    #[derive(Debug)]
+    pub struct B {
+        pub field: u32,
+    }
+    impl B {
+        pub fn new() -> Self {
+            Self { field: 0 }
+        }
+    }
+}
+pub mod a {
+    pub use crate::c::B;
+}
+#[doc(hidden)]
+pub fn hidden() {}
+pub use c::B as Renamed;
+    "#;
+    let json = rustdoc_json_for_lib(lib);
+    let builder = public_api::Builder::from_rustdoc_json(&json.json_path)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .omit_auto_derived_impls(true)
+        .omit_doc_hidden(true)
+        .collapse_reexports(true)
+        .exclude_paths(["lib::c::B::new"]);
+    let rendered = |api: &public_api::PublicApi| -> Vec<(String, Vec<String>)> {
+        api.items()
+            .map(|item| (item.to_string(), item.reexport_paths().to_vec()))
+            .collect()
+    };
+
+    let unfiltered = public_api::Builder::from_rustdoc_json(&json.json_path)
+        .build()
+        .unwrap();
+    let filtered = builder.apply_filters(unfiltered).unwrap();
+    let built = builder.clone().build().unwrap();
+    assert_eq!(rendered(&filtered), rendered(&built));
+
+    // Filters are idempotent, so a snapshot written with them is not changed
+    let filtered_again = builder.apply_filters(built).unwrap();
+    assert_eq!(rendered(&filtered_again), rendered(&filtered));
+}
+
+#[test]
+fn removed_reexport_alias_is_a_changed_item() {
+    let build = |lib: &str| {
+        let json = rustdoc_json_for_lib(lib);
+        public_api::Builder::from_rustdoc_json(&json.json_path)
+            .omit_blanket_impls(true)
+            .omit_auto_trait_impls(true)
+            .collapse_reexports(true)
+            .build()
+            .unwrap()
+    };
+    let old = build("pub mod c { pub struct B; }\npub use c::B;");
+    let new = build("pub mod c { pub struct B; }");
+
+    let diff = public_api::diff::PublicApiDiff::between(old, new);
+    assert!(diff.removed.is_empty());
+    assert!(diff.added.is_empty());
+    let changed: Vec<_> = diff
+        .changed
+        .iter()
+        .map(|changed| {
+            (
+                changed.old.to_string(),
+                changed.old.reexport_paths(),
+                changed.new.reexport_paths(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changed,
+        vec![(
+            "pub struct lib::c::B".to_owned(),
+            &["lib::B".to_owned()][..],
+            &[][..]
+        )]
+    );
+    let reasons: Vec<_> = diff.classify().changed.iter().map(|c| c.reason).collect();
+    assert_eq!(
+        reasons,
+        vec![public_api::semver::SemverReason::ReexportRemoved]
+    );
+}
+
+#[test]
+fn item_kinds_and_paths() {
+    let json = rustdoc_json_for_lib(
+        r#"
+pub mod m {
+    pub struct S<T> {
+        pub field: T,
+    }
+    impl<T> S<T> {
+        pub fn method(&self) {}
+    }
+    impl<T: Clone> Clone for S<T> {
+        fn clone(&self) -> Self {
+            Self { field: self.field.clone() }
+        }
+    }
+}
+pub fn function() {}
+    "#,
+    );
+    let api = public_api::Builder::from_rustdoc_json(json.json_path)
+        .omit_blanket_impls(true)
+        .omit_auto_trait_impls(true)
+        .build()
+        .unwrap();
+
+    let kinds_and_paths: Vec<(String, String)> = api
+        .items()
+        .map(|item| (item.kind().unwrap().to_string(), item.path().join("::")))
+        .collect();
+    assert_eq!(
+        kinds_and_paths,
+        [
+            ("module", "lib"),
+            ("module", "lib::m"),
+            ("struct", "lib::m::S"),
+            ("struct_field", "lib::m::S::field"),
+            ("inherent_impl", "lib::m::S"),
+            ("method", "lib::m::S::method"),
+            ("trait_impl", "lib::m::S"),
+            ("method", "lib::m::S::clone"),
+            ("function", "lib::function"),
+        ]
+        .map(|(kind, path)| (kind.to_owned(), path.to_owned()))
+    );
+    assert!(
+        api.items().any(
+            |item| item.kind() == Some(public_api::ItemKind::Impl(public_api::ImplKind::Trait))
+        )
+    );
+}
+
+#[test]
+fn query() {
+    let json = rustdoc_json_for_lib(
+        r#"
+pub mod io {
+    pub struct Reader;
+    impl Reader {
+        pub fn read(&mut self) -> Result<u8, ()> {
+            Ok(0)
+        }
+        pub async fn read_async(&mut self) -> u8 {
+            0
+        }
+        pub fn peek(&self) -> Option<u8> {
+            None
+        }
+        pub unsafe fn read_unchecked(self) -> u8 {
+            0
+        }
+    }
+    impl Clone for Reader {
+        fn clone(&self) -> Self {
+            Self
+        }
+    }
+}
+pub fn read_all() -> std::io::Result<Vec<u8>> {
+    Ok(vec![])
+}
+pub fn not_a_result() -> MyResult {
+    MyResult
+}
+pub struct MyResult;
+    "#,
+    );
+    let query = |predicates: &[&str]| -> Vec<String> {
+        public_api::Builder::from_rustdoc_json(&json.json_path)
+            .omit_blanket_impls(true)
+            .omit_auto_trait_impls(true)
+            .omit_auto_derived_impls(true)
+            .query(Some(public_api::query::Query::new(predicates).unwrap()))
+            .build()
+            .unwrap()
+            .items()
+            .map(|item| item.path().join("::"))
+            .collect()
+    };
+
+    assert_eq!(
+        query(&["kind:fn", "returns:Result"]),
+        ["lib::io::Reader::read", "lib::read_all"]
+    );
+    assert_eq!(
+        query(&["self:mut", "returns:impl Future"]),
+        ["lib::io::Reader::read_async"]
+    );
+    assert_eq!(query(&["unsafe:true"]), ["lib::io::Reader::read_unchecked"]);
+    assert_eq!(query(&["self:value"]), ["lib::io::Reader::read_unchecked"]);
+    assert_eq!(query(&["impl:Clone"]), ["lib::io::Reader"]);
+    assert_eq!(
+        query(&["path:crate::io::**", "name:read*"]),
+        [
+            "lib::io::Reader::read",
+            "lib::io::Reader::read_async",
+            "lib::io::Reader::read_unchecked",
+        ]
+    );
+    assert_eq!(
+        query(&["kind:struct,mod", "path:crate::*"]),
+        ["lib::io", "lib::MyResult"]
+    );
+
+    assert!(public_api::query::Query::new(["colour:red"]).is_err());
+}
+
 #[test]
 fn diff_with_added_items() {
     // Create independent build dirs so all tests can run in parallel
@@ -193,6 +698,75 @@ pub enum Foo {
     assert_no_textual_public_api_diff(v1.json_path, v2.json_path);
 }
 
+#[test]
+fn semver_classification_of_added_items() {
+    use public_api::semver::{SemverImpact, SemverReason};
+
+    let v1 = rustdoc_json_for_lib(
+        r#"
+pub enum Exhaustive { A }
+#[non_exhaustive]
+pub enum NonExhaustive { A }
+pub struct Plain { pub a: u8 }
+pub trait Trait {}
+    "#,
+    );
+
+    let v2 = rustdoc_json_for_lib(
+        r#"
+pub enum Exhaustive { A, B }
+#[non_exhaustive]
+pub enum NonExhaustive { A, B }
+pub struct Plain { pub a: u8, pub b: u8 }
+pub trait Trait {
+    fn required(&self);
+    fn defaulted(&self) {}
+}
+pub fn new_fn() {}
+    "#,
+    );
+
+    let old = public_api::Builder::from_rustdoc_json(v1.json_path)
+        .build()
+        .unwrap();
+    let new = public_api::Builder::from_rustdoc_json(v2.json_path)
+        .build()
+        .unwrap();
+    let diff = public_api::diff::PublicApiDiff::between(old, new);
+    let classification = diff.classify();
+
+    let reason_for = |needle: &str| {
+        classification
+            .added
+            .iter()
+            .find(|c| c.entry.to_string() == needle)
+            .unwrap_or_else(|| panic!("{needle} not found in {diff:#?}"))
+            .reason
+    };
+    assert_eq!(
+        reason_for("pub lib::Exhaustive::B"),
+        SemverReason::VariantAddedToExhaustiveEnum
+    );
+    assert_eq!(
+        reason_for("pub lib::NonExhaustive::B"),
+        SemverReason::ItemAdded
+    );
+    assert_eq!(
+        reason_for("pub lib::Plain::b: u8"),
+        SemverReason::FieldAddedToExhaustiveStruct
+    );
+    assert_eq!(
+        reason_for("pub fn lib::Trait::required(&self)"),
+        SemverReason::RequiredTraitItemAdded
+    );
+    assert_eq!(
+        reason_for("pub fn lib::Trait::defaulted(&self)"),
+        SemverReason::DefaultedTraitItemAdded
+    );
+    assert_eq!(reason_for("pub fn lib::new_fn()"), SemverReason::ItemAdded);
+    assert_eq!(classification.required_bump(), SemverImpact::Major);
+}
+
 #[test]
 fn diff_with_removed_items() {
     // Create independent build dirs so all tests can run in parallel
@@ -206,6 +780,98 @@ fn diff_with_removed_items() {
     );
 }
 
+#[test]
+fn diff_with_text_snapshot() {
+    use public_api::{PublicApi, diff::PublicApiDiff};
+
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let build = |json| {
+        public_api::Builder::from_rustdoc_json(json)
+            .build()
+            .unwrap()
+    };
+    let old = build(rustdoc_json_path_for_crate(
+        "../test-apis/example_api-v0.1.0",
+        &build_dir,
+    ));
+    let new = build(rustdoc_json_path_for_crate(
+        "../test-apis/example_api-v0.2.0",
+        &build_dir2,
+    ));
+
+    let old_snapshot = PublicApi::from_text_snapshot(&old.to_string());
+    let new_snapshot = PublicApi::from_text_snapshot(&new.to_string());
+    assert_eq!(
+        sorted_lines(&old_snapshot.to_string()),
+        sorted_lines(&old.to_string())
+    );
+
+    let as_text = |diff: PublicApiDiff| {
+        (
+            diff.removed
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            diff.changed
+                .iter()
+                .map(|c| format!("{} -> {}", c.old, c.new))
+                .collect::<Vec<_>>(),
+            diff.added
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(
+        as_text(PublicApiDiff::between(old_snapshot, new_snapshot)),
+        as_text(PublicApiDiff::between(old, new)),
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn diff_with_deserialized_public_api() {
+    use public_api::{PublicApi, diff::PublicApiDiff, tokens::Token};
+
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let new = || {
+        public_api::Builder::from_rustdoc_json(&new_json)
+            .build()
+            .unwrap()
+    };
+
+    let old = public_api::Builder::from_rustdoc_json(&old_json)
+        .build()
+        .unwrap();
+    let stored = serde_json::to_string(&old).unwrap();
+    let restored: PublicApi = serde_json::from_str(&stored).unwrap();
+    assert_eq!(restored.to_string(), old.to_string());
+
+    let expected = PublicApiDiff::between(old, new());
+    let actual = PublicApiDiff::between(restored, new());
+    assert_eq!(actual, expected);
+
+    let stored_diff = serde_json::to_string(&actual).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PublicApiDiff>(&stored_diff).unwrap(),
+        expected
+    );
+
+    // The documented format of tokens
+    assert_eq!(
+        serde_json::to_string(&[Token::Self_("self".to_owned()), Token::Whitespace]).unwrap(),
+        r#"[{"kind":"self","text":"self"},{"kind":"whitespace"}]"#
+    );
+}
+
 #[test]
 fn comprehensive_api() {
     // Create independent build dir so all tests can run in parallel
@@ -340,11 +1006,27 @@ struct LibWithJson {
     _root: TempDir,
 }
 
+fn sorted_lines(s: &str) -> Vec<&str> {
+    let mut lines: Vec<_> = s.lines().collect();
+    lines.sort_unstable();
+    lines
+}
+
 fn write_file(root: &TempDir, file: &str, content: &str) {
     fs::write(root.path().join(file), content).unwrap();
 }
 
 fn rustdoc_json_for_lib(lib: &str) -> LibWithJson {
+    let root = temp_lib(lib);
+
+    LibWithJson {
+        json_path: rustdoc_json_path_for_temp_crate(&root),
+        _root: root,
+    }
+}
+
+/// Sets up a temporary crate with the given `lib.rs`.
+fn temp_lib(lib: &str) -> TempDir {
     let root = tempdir().unwrap();
 
     write_file(
@@ -362,10 +1044,7 @@ fn rustdoc_json_for_lib(lib: &str) -> LibWithJson {
 
     write_file(&root, "lib.rs", lib);
 
-    LibWithJson {
-        json_path: rustdoc_json_path_for_temp_crate(&root),
-        _root: root,
-    }
+    root
 }
 
 fn assert_public_api_diff(
@@ -90,6 +90,7 @@ fn cargo_rustdoc_command(options: &Builder) -> Result<Command, BuildError> {
         package,
         package_target,
         document_private_items,
+        document_hidden_items,
         cap_lints,
         envs,
     } = options;
@@ -156,6 +157,9 @@ fn cargo_rustdoc_command(options: &Builder) -> Result<Command, BuildError> {
     if *document_private_items {
         command.arg("--document-private-items");
     }
+    if *document_hidden_items {
+        command.arg("--document-hidden-items");
+    }
     if let Some(cap_lints) = cap_lints {
         command.args(["--cap-lints", cap_lints]);
     }
@@ -293,6 +297,7 @@ pub struct Builder {
     package: Option<String>,
     package_target: PackageTarget,
     document_private_items: bool,
+    document_hidden_items: bool,
     cap_lints: Option<String>,
     envs: HashMap<OsString, OsString>,
 }
@@ -313,6 +318,7 @@ impl Default for Builder {
             package: None,
             package_target: PackageTarget::default(),
             document_private_items: false,
+            document_hidden_items: false,
             cap_lints: Some(String::from("warn")),
             envs: HashMap::new(),
         }
@@ -441,6 +447,14 @@ impl Builder {
         self
     }
 
+    /// Whether to pass `--document-hidden-items` to `cargo rustdoc`, which
+    /// includes `#[doc(hidden)]` items in the rustdoc JSON. Default: `false`
+    #[must_use]
+    pub fn document_hidden_items(mut self, document_hidden_items: bool) -> Self {
+        self.document_hidden_items = document_hidden_items;
+        self
+    }
+
     /// What to pass as `--cap-lints` to rustdoc JSON build command
     #[must_use]
     pub fn cap_lints(mut self, cap_lints: Option<impl AsRef<str>>) -> Self {
@@ -7,3 +7,9 @@ pub struct AStruct {
     #[cfg(feature = "feature_c")]
     pub feature_c: (),
 }
+
+#[cfg(any(feature = "feature_b", feature = "feature_c"))]
+pub fn feature_b_or_c() {}
+
+#[cfg(all(feature = "feature_b", feature = "feature_c"))]
+pub fn feature_b_and_c() {}
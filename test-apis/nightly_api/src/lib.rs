@@ -1,6 +1,7 @@
-//! This crate is not used in regression tests. Its purposes is to make it easy
-//! for `cargo public-api` maintainers to experiment with the rustdoc JSON that
-//! unstable Rust can produce.
+//! Apart from the stability items at the bottom, which are used to test
+//! `--omit unstable`, this crate is not used in regression tests. Its purposes
+//! is to make it easy for `cargo public-api` maintainers to experiment with the
+//! rustdoc JSON that unstable Rust can produce.
 //!
 //! The situation is a bit subtle because `cargo public-api` does not support
 //! listing the public API of Rust crates that use unstable Rust features. It
@@ -34,13 +35,37 @@
 //! [1]: https://github.com/rust-lang/rust/pull/109410
 
 #![feature(inherent_associated_types)]
+#![feature(staged_api)]
+#![allow(internal_features, incomplete_features)]
+#![stable(feature = "nightly_api", since = "0.1.0")]
 
+#[stable(feature = "nightly_api", since = "0.1.0")]
 pub struct StructWithInherentAssociatedType;
 
 impl StructWithInherentAssociatedType {
+    #[stable(feature = "nightly_api", since = "0.1.0")]
     pub type InherentAssociatedType = u8;
 
+    #[stable(feature = "nightly_api", since = "0.1.0")]
     pub fn inherent_associated_type_output(&self) -> Self::InherentAssociatedType {
         1
     }
 }
+
+/// Stable items are listed without annotation.
+#[stable(feature = "nightly_api", since = "0.1.0")]
+pub fn stable_fn() {}
+
+#[unstable(feature = "unstable_fn", issue = "none")]
+pub fn unstable_fn() {}
+
+/// Items inside unstable items are unstable too.
+#[unstable(feature = "unstable_mod", issue = "none")]
+pub mod unstable_mod {
+    pub fn inner_fn() {}
+}
+
+/// Unstable with `--unstable-feature nightly`.
+#[cfg(feature = "nightly")]
+#[stable(feature = "nightly_api", since = "0.1.0")]
+pub fn nightly_fn() {}